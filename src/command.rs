@@ -1,23 +1,28 @@
 use crate::ha::values::EntityCategory;
-use anyhow::Error;
+use anyhow::{Context as _, Error, ensure};
 use async_trait::async_trait;
-use serde::Serialize;
+use regex::Regex;
+use serde::Deserialize;
 use std::borrow::Cow;
 
 #[async_trait]
 pub trait Command: 'static {
     fn topic(&self) -> &str;
     fn discovery_data(&self) -> Vec<CommandDiscovery<'_>>;
-    async fn execute(&self) -> Result<(), Error>;
+    /// Execute the command with the payload received, which has been validated against the input.
+    async fn execute(&self, payload: &str) -> Result<(), Error>;
 }
 
-#[derive(Serialize)]
 pub struct CommandDiscovery<'a> {
     pub id: Cow<'a, str>,
     pub name: Cow<'a, str>,
     pub icon: Option<&'static str>,
     pub entity_category: Option<EntityCategory>,
     pub device_class: Option<&'static str>,
+    pub input: CommandInput,
+    /// Topic the state of the entity is published to.
+    pub state_topic: Option<&'a str>,
+    pub value_template: Option<&'static str>,
 }
 
 impl<'a> CommandDiscovery<'a> {
@@ -28,6 +33,9 @@ impl<'a> CommandDiscovery<'a> {
             icon: Some(init.icon),
             entity_category: None,
             device_class: None,
+            input: CommandInput::Button,
+            state_topic: None,
+            value_template: None,
         }
     }
 
@@ -40,6 +48,17 @@ impl<'a> CommandDiscovery<'a> {
         self.device_class = Some(device_class);
         self
     }
+
+    pub fn with_input(mut self, input: CommandInput) -> Self {
+        self.input = input;
+        self
+    }
+
+    pub fn with_state(mut self, state_topic: &'a str, value_template: &'static str) -> Self {
+        self.state_topic = Some(state_topic);
+        self.value_template = Some(value_template);
+        self
+    }
 }
 
 pub struct CommandDiscoveryInit<S> {
@@ -47,3 +66,177 @@ pub struct CommandDiscoveryInit<S> {
     pub name: S,
     pub icon: &'static str,
 }
+
+/// What a command takes from Home Assistant, which decides the type of its entity.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CommandInput {
+    /// A button without any meaningful payload.
+    #[default]
+    Button,
+    /// A text entity.
+    Text {
+        /// Minimum length of the text. (Default: 0)
+        #[serde(default)]
+        min: usize,
+        /// Maximum length of the text. (Default: 255)
+        #[serde(default = "CommandInput::default_text_max")]
+        max: usize,
+        /// Regular expression the whole text must match.
+        pattern: Option<String>,
+    },
+    /// A number entity.
+    Number {
+        /// Minimum value. (Default: 1)
+        #[serde(default = "CommandInput::default_number_min")]
+        min: f64,
+        /// Maximum value. (Default: 100)
+        #[serde(default = "CommandInput::default_number_max")]
+        max: f64,
+        /// Step of the value. (Default: 1)
+        #[serde(default = "CommandInput::default_number_step")]
+        step: f64,
+    },
+    /// A select entity.
+    Select {
+        /// Options to select from.
+        options: Vec<String>,
+    },
+    /// A switch taking `ON` or `OFF`.
+    #[serde(skip)]
+    Switch,
+    /// An update entity taking `install`.
+    #[serde(skip)]
+    Update,
+}
+
+impl CommandInput {
+    fn default_text_max() -> usize {
+        255
+    }
+    fn default_number_min() -> f64 {
+        1.
+    }
+    fn default_number_max() -> f64 {
+        100.
+    }
+    fn default_number_step() -> f64 {
+        1.
+    }
+
+    /// Home Assistant platform of the entity taking this input.
+    pub fn platform(&self) -> &'static str {
+        match self {
+            CommandInput::Button => "button",
+            CommandInput::Text { .. } => "text",
+            CommandInput::Number { .. } => "number",
+            CommandInput::Select { .. } => "select",
+            CommandInput::Switch => "switch",
+            CommandInput::Update => "update",
+        }
+    }
+
+    /// Check that the input itself is well-formed.
+    pub fn check(&self) -> Result<(), Error> {
+        match self {
+            CommandInput::Button | CommandInput::Switch | CommandInput::Update => {}
+            CommandInput::Text { min, max, pattern } => {
+                ensure!(min <= max, "Text min is larger than max");
+                if let Some(pattern) = pattern {
+                    Regex::new(pattern).context("Invalid text pattern")?;
+                }
+            }
+            CommandInput::Number { min, max, step } => {
+                ensure!(min <= max, "Number min is larger than max");
+                ensure!(*step > 0., "Number step must be positive");
+            }
+            CommandInput::Select { options } => {
+                ensure!(!options.is_empty(), "Select options are empty");
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that the payload received is acceptable for the input.
+    pub fn validate(&self, payload: &str) -> Result<(), Error> {
+        match self {
+            CommandInput::Button => {}
+            CommandInput::Text { min, max, pattern } => {
+                let len = payload.chars().count();
+                ensure!(
+                    (*min..=*max).contains(&len),
+                    "Text length {len} is out of range {min}..={max}",
+                );
+                if let Some(pattern) = pattern {
+                    let regex =
+                        Regex::new(&format!("^(?:{pattern})$")).context("Invalid text pattern")?;
+                    ensure!(regex.is_match(payload), "Text doesn't match {pattern}");
+                }
+            }
+            CommandInput::Number { min, max, step } => {
+                // Surrounding whitespaces are rejected, as the payload is passed on as is.
+                let value = payload.parse::<f64>().context("Failed to parse number")?;
+                ensure!(
+                    (*min..=*max).contains(&value),
+                    "Number {value} is out of range {min}..={max}",
+                );
+                let steps = (value - min) / step;
+                ensure!(
+                    (steps - steps.round()).abs() < 1e-6,
+                    "Number {value} is not a multiple of {step} from {min}",
+                );
+            }
+            CommandInput::Select { options } => {
+                ensure!(
+                    options.iter().any(|option| option == payload),
+                    "{payload} is not one of the options",
+                );
+            }
+            CommandInput::Switch => {
+                ensure!(
+                    matches!(payload, "ON" | "OFF"),
+                    "{payload} is not ON or OFF"
+                );
+            }
+            CommandInput::Update => {
+                ensure!(payload == "install", "{payload} is not install");
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CommandInput;
+
+    #[test]
+    fn test_validate() {
+        let text = CommandInput::Text {
+            min: 1,
+            max: 5,
+            pattern: Some("[a-z]+".into()),
+        };
+        assert!(text.validate("abc").is_ok());
+        assert!(text.validate("").is_err());
+        assert!(text.validate("abcdef").is_err());
+        assert!(text.validate("ab1").is_err());
+
+        let number = CommandInput::Number {
+            min: 0.,
+            max: 10.,
+            step: 0.5,
+        };
+        assert!(number.validate("2.5").is_ok());
+        assert!(number.validate("11").is_err());
+        assert!(number.validate("foo").is_err());
+        assert!(number.validate("2.7").is_err());
+        assert!(number.validate(" 2").is_err());
+
+        let select = CommandInput::Select {
+            options: vec!["on".into(), "off".into()],
+        };
+        assert!(select.validate("on").is_ok());
+        assert!(select.validate("auto").is_err());
+    }
+}
@@ -1,27 +1,68 @@
-use crate::command::Command;
+use crate::command::{Command, CommandInput};
 use crate::commands::Commands;
+use crate::metrics::{METRICS, Metrics};
 use anyhow::{Context, Error};
 use log::{debug, error, warn};
 use rumqttc::{AsyncClient, QoS};
 use std::collections::HashMap;
 
 pub struct CommandSubscriber<'a> {
-    topic_to_command: HashMap<&'a str, &'a dyn Command>,
+    topic_to_command: HashMap<&'a str, (&'a dyn Command, CommandInput)>,
 }
 
 impl<'a> CommandSubscriber<'a> {
     pub fn new(commands: &'a Commands) -> Self {
-        let topic_to_command = [
-            (
-                commands.reboot_command.topic(),
-                &commands.reboot_command as &dyn Command,
-            ),
-            (
-                commands.suspend_command.topic(),
-                &commands.suspend_command as &dyn Command,
-            ),
-        ]
-        .into();
+        let mut all_commands = vec![
+            &commands.reboot_command as &dyn Command,
+            &commands.suspend_command as &dyn Command,
+            &commands.apt_update_command as &dyn Command,
+            &commands.apt_upgrade_command as &dyn Command,
+            &commands.apt_dist_upgrade_command as &dyn Command,
+            &commands.restart_services_command as &dyn Command,
+        ];
+        all_commands.extend(
+            commands
+                .snap_refresh_command
+                .iter()
+                .map(|command| command as &dyn Command),
+        );
+        all_commands.extend(
+            commands
+                .lock_sessions_command
+                .iter()
+                .map(|command| command as &dyn Command),
+        );
+        all_commands.extend(
+            commands
+                .governor_command
+                .iter()
+                .map(|command| command as &dyn Command),
+        );
+        all_commands.extend(
+            commands
+                .custom_commands
+                .iter()
+                .map(|command| command as &dyn Command),
+        );
+        all_commands.extend(
+            commands
+                .switch_commands
+                .iter()
+                .map(|command| command as &dyn Command),
+        );
+        let topic_to_command = all_commands
+            .into_iter()
+            .map(|command| {
+                // All entities of a command share its topic, so they must take the same input.
+                let input = command
+                    .discovery_data()
+                    .into_iter()
+                    .next()
+                    .map(|discovery| discovery.input)
+                    .unwrap_or_default();
+                (command.topic(), (command, input))
+            })
+            .collect();
         Self { topic_to_command }
     }
 
@@ -36,14 +77,21 @@ impl<'a> CommandSubscriber<'a> {
         Ok(())
     }
 
-    pub async fn handle_message(&self, topic: &str) {
+    pub async fn handle_message(&self, topic: &str, payload: &[u8]) {
         debug!("Received command message on {topic}");
 
         match self.topic_to_command.get(topic) {
-            Some(command) => {
-                if let Err(e) = command.execute().await {
-                    let (_, id) = command.topic().rsplit_once('/').unwrap();
+            Some((command, input)) => {
+                let (_, id) = command.topic().rsplit_once('/').unwrap();
+                let payload = String::from_utf8_lossy(payload);
+                if let Err(e) = input.validate(&payload) {
+                    warn!("Rejected payload for command {id}: {e}");
+                    return;
+                }
+                Metrics::increment(&METRICS.command_executions);
+                if let Err(e) = command.execute(&payload).await {
                     error!("Failed to execute command {id}: {e}");
+                    Metrics::increment(&METRICS.command_failures);
                 }
             }
             None => warn!("Received message on unknown topic {topic}"),
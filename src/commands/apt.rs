@@ -0,0 +1,310 @@
+use super::background::BackgroundJob;
+use crate::command::{Command, CommandDiscovery, CommandDiscoveryInit, CommandInput};
+use crate::ha::values::EntityCategory;
+use crate::utils::serialize::serialize_timestamp_opt;
+use anyhow::{Context, Error, anyhow, bail, ensure};
+use async_trait::async_trait;
+use log::{debug, error, info};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use time::OffsetDateTime;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process;
+use tokio::select;
+use tokio::sync::Notify;
+use tokio::time::timeout;
+
+/// Number of log lines kept from the current or last job.
+const MAX_LOG_LINES: usize = 10;
+/// Maximum time for a job, after which it's killed, e.g. when it hangs on a prompt.
+const JOB_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+/// Options passed to every `apt-get` invocation.
+///
+/// Together with the action, they need to match the sudoers rule exactly.
+const APT_GET_OPTIONS: &[&str] = &[
+    "-y",
+    "-q",
+    "-o",
+    "APT::Status-Fd=1",
+    "-o",
+    "Dpkg::Options::=--force-confdef",
+    "-o",
+    "Dpkg::Options::=--force-confold",
+];
+
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AptAction {
+    Update,
+    Upgrade,
+    DistUpgrade,
+}
+
+impl AptAction {
+    fn id(self) -> &'static str {
+        match self {
+            AptAction::Update => "apt_update",
+            AptAction::Upgrade => "apt_upgrade",
+            AptAction::DistUpgrade => "apt_dist_upgrade",
+        }
+    }
+
+    fn arg(self) -> &'static str {
+        match self {
+            AptAction::Update => "update",
+            AptAction::Upgrade => "upgrade",
+            AptAction::DistUpgrade => "dist-upgrade",
+        }
+    }
+}
+
+/// Runs an `apt-get` action in the background.
+///
+/// The upgrade action is exposed as the install action of the update entity,
+/// while the others are buttons.
+pub struct AptCommand {
+    action: AptAction,
+    topic: Box<str>,
+    /// Topic of the APT sensor, which the update entity takes its state from.
+    state_topic: Box<str>,
+    jobs: Arc<AptJobs>,
+}
+
+impl AptCommand {
+    pub fn new(topic_base: &str, state_topic: &str, action: AptAction, jobs: Arc<AptJobs>) -> Self {
+        Self {
+            action,
+            topic: format!("{topic_base}/{}", action.id()).into(),
+            state_topic: state_topic.into(),
+            jobs,
+        }
+    }
+
+    /// Jobs shared by all APT commands, reported by sensors.
+    pub fn jobs(&self) -> Arc<AptJobs> {
+        self.jobs.clone()
+    }
+}
+
+#[async_trait]
+impl Command for AptCommand {
+    fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    fn discovery_data(&self) -> Vec<CommandDiscovery<'_>> {
+        let discovery = match self.action {
+            AptAction::Update => CommandDiscovery::new(CommandDiscoveryInit {
+                id: self.action.id(),
+                name: "APT update package lists",
+                icon: "mdi:refresh",
+            }),
+            AptAction::Upgrade => CommandDiscovery::new(CommandDiscoveryInit {
+                id: self.action.id(),
+                name: "APT upgrades",
+                icon: "mdi:package-up",
+            })
+            .with_input(CommandInput::Update)
+            .with_state(&self.state_topic, "{{ value_json.update | tojson }}"),
+            AptAction::DistUpgrade => CommandDiscovery::new(CommandDiscoveryInit {
+                id: self.action.id(),
+                name: "APT dist-upgrade",
+                icon: "mdi:package-up",
+            }),
+        };
+        vec![discovery.with_entity_category(EntityCategory::Config)]
+    }
+
+    async fn execute(&self, _payload: &str) -> Result<(), Error> {
+        let action = self.action;
+        let jobs = self.jobs.clone();
+        let spawned = self.jobs.job.spawn(async move {
+            jobs.start(action);
+            let result = match timeout(JOB_TIMEOUT, run(action, &jobs)).await {
+                Ok(result) => result,
+                Err(_) => Err(anyhow!("Timed out")),
+            };
+            if let Err(e) = &result {
+                error!("Failed to run apt-get {}: {e}", action.arg());
+            }
+            jobs.finish(result.is_ok());
+        });
+        if !spawned {
+            bail!("Another apt-get job is already running");
+        }
+        info!("Started apt-get {}", action.arg());
+        Ok(())
+    }
+}
+
+async fn run(action: AptAction, jobs: &AptJobs) -> Result<(), Error> {
+    let mut child = process::Command::new("sudo")
+        .args(["-n", "/usr/bin/apt-get"])
+        .args(APT_GET_OPTIONS)
+        .arg(action.arg())
+        .env("LANG", "C")
+        // Kept by sudo as allowed in sudoers, so that no prompt waits for input.
+        .env("DEBIAN_FRONTEND", "noninteractive")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("Failed to invoke apt-get")?;
+
+    let stdout = child.stdout.take().context("Failed to take stdout")?;
+    let stderr = child.stderr.take().context("Failed to take stderr")?;
+    let mut stdout = BufReader::new(stdout).lines();
+    let mut stderr = BufReader::new(stderr).lines();
+    let (mut stdout_done, mut stderr_done) = (false, false);
+    loop {
+        select! {
+            line = stdout.next_line(), if !stdout_done => match line? {
+                Some(line) => jobs.handle_line(line),
+                None => stdout_done = true,
+            },
+            line = stderr.next_line(), if !stderr_done => match line? {
+                Some(line) => jobs.handle_line(line),
+                None => stderr_done = true,
+            },
+            else => break,
+        }
+    }
+
+    let status = child
+        .wait()
+        .await
+        .context("Failed to wait for apt-get command")?;
+    ensure!(status.success(), "apt-get {} failed", action.arg());
+    Ok(())
+}
+
+/// State of APT jobs, shared between the commands running them and the sensors reporting them.
+#[derive(Default)]
+pub struct AptJobs {
+    /// Only one job may run at a time, whichever the action.
+    job: BackgroundJob,
+    status: Mutex<JobStatus>,
+    /// Notified whenever the status changes, for the job sensor.
+    pub status_changed: Notify,
+    /// Notified whenever the status changes, for the APT sensor.
+    pub progress_changed: Notify,
+    /// Notified when a job finishes, for sensors affected by it.
+    pub finished: Notify,
+}
+
+impl AptJobs {
+    pub fn status(&self) -> JobStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    fn start(&self, action: AptAction) {
+        let mut status = self.status.lock().unwrap();
+        *status = JobStatus {
+            action: Some(action),
+            state: JobState::Running,
+            percentage: None,
+            log: VecDeque::new(),
+            started_at: Some(OffsetDateTime::now_utc()),
+            finished_at: None,
+        };
+        drop(status);
+        self.notify_changed();
+    }
+
+    fn handle_line(&self, line: String) {
+        let mut status = self.status.lock().unwrap();
+        match parse_status_line(&line) {
+            Some(percentage) => {
+                debug!("APT progress: {percentage}%");
+                status.percentage = Some(percentage);
+            }
+            None => {
+                if status.log.len() >= MAX_LOG_LINES {
+                    status.log.pop_front();
+                }
+                status.log.push_back(line);
+            }
+        }
+        drop(status);
+        self.notify_changed();
+    }
+
+    fn finish(&self, succeeded: bool) {
+        let mut status = self.status.lock().unwrap();
+        status.state = match succeeded {
+            true => JobState::Succeeded,
+            false => JobState::Failed,
+        };
+        status.finished_at = Some(OffsetDateTime::now_utc());
+        drop(status);
+        self.notify_changed();
+        self.finished.notify_one();
+    }
+
+    fn notify_changed(&self) {
+        self.status_changed.notify_one();
+        self.progress_changed.notify_one();
+    }
+}
+
+#[derive(Clone, Default, Serialize)]
+pub struct JobStatus {
+    pub action: Option<AptAction>,
+    pub state: JobState,
+    pub percentage: Option<f32>,
+    pub log: VecDeque<String>,
+    #[serde(serialize_with = "serialize_timestamp_opt")]
+    pub started_at: Option<OffsetDateTime>,
+    #[serde(serialize_with = "serialize_timestamp_opt")]
+    pub finished_at: Option<OffsetDateTime>,
+}
+
+impl JobStatus {
+    pub fn is_running(&self) -> bool {
+        matches!(self.state, JobState::Running)
+    }
+}
+
+#[derive(Clone, Copy, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    #[default]
+    Idle,
+    Running,
+    Succeeded,
+    Failed,
+}
+
+/// Parse the percentage from a line of `APT::Status-Fd` output,
+/// e.g. `pmstatus:libc6:42.8571:Unpacking libc6 (amd64)`.
+fn parse_status_line(line: &str) -> Option<f32> {
+    let line = line
+        .strip_prefix("dlstatus:")
+        .or_else(|| line.strip_prefix("pmstatus:"))?;
+    let mut fields = line.splitn(3, ':');
+    let _package = fields.next()?;
+    fields.next()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_status_line;
+
+    #[test]
+    fn test_parse_status_line() {
+        assert_eq!(
+            parse_status_line("pmstatus:libc6:42.5:Unpacking libc6 (amd64)"),
+            Some(42.5)
+        );
+        assert_eq!(
+            parse_status_line("dlstatus:1:9.5:Retrieving file 1 of 3"),
+            Some(9.5)
+        );
+        assert_eq!(parse_status_line("Reading package lists..."), None);
+    }
+}
@@ -0,0 +1,26 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// A job run in the background, of which only one run may be in progress at a time.
+///
+/// Commands are executed one by one, so a slow job run inline would block all other commands
+/// until it finishes.
+#[derive(Default)]
+pub struct BackgroundJob {
+    running: Arc<AtomicBool>,
+}
+
+impl BackgroundJob {
+    /// Spawn a run of the job, or return `false` if the previous run is still in progress.
+    pub fn spawn(&self, run: impl Future<Output = ()> + Send + 'static) -> bool {
+        if self.running.swap(true, Ordering::AcqRel) {
+            return false;
+        }
+        let running = self.running.clone();
+        tokio::spawn(async move {
+            run.await;
+            running.store(false, Ordering::Release);
+        });
+        true
+    }
+}
@@ -0,0 +1,252 @@
+use super::background::BackgroundJob;
+use crate::command::{Command, CommandDiscovery, CommandDiscoveryInit, CommandInput};
+use crate::config;
+use crate::metrics::{METRICS, Metrics};
+use crate::utils::serialize::serialize_timestamp;
+use crate::utils::snake_case::make_snake_case;
+use anyhow::{Context as _, Error, bail, ensure};
+use async_trait::async_trait;
+use log::{error, info};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use time::OffsetDateTime;
+use tokio::process;
+use tokio::sync::Notify;
+use tokio::time::timeout;
+
+/// Maximum length of output kept from the last run.
+const MAX_OUTPUT_LEN: usize = 1024;
+
+pub struct CustomCommand {
+    id: String,
+    topic: Box<str>,
+    name: String,
+    icon: &'static str,
+    command: Vec<String>,
+    input: CommandInput,
+    user: Option<String>,
+    working_directory: Option<PathBuf>,
+    timeout: Duration,
+    job: BackgroundJob,
+    last_run: Arc<LastRun>,
+}
+
+impl CustomCommand {
+    pub fn new(topic_base: &str, config: &config::CustomCommand) -> Result<Self, Error> {
+        ensure!(!config.command.is_empty(), "Command is empty");
+        config.input.check().context("Invalid input")?;
+        // Free text could otherwise inject options into the command, possibly run as another user.
+        let takes_value = config.command.iter().any(|arg| arg.contains("{value}"));
+        let is_free_text = matches!(config.input, CommandInput::Text { pattern: None, .. });
+        ensure!(
+            !(takes_value && is_free_text),
+            "Text input passed to the command requires a pattern",
+        );
+        let id = format!("custom_{}", make_snake_case(&config.id));
+        let topic = format!("{topic_base}/{id}").into_boxed_str();
+        // Discovery requires a static string, and custom commands live as long as the process.
+        let icon = match &config.icon {
+            Some(icon) => icon.clone().leak(),
+            None => "mdi:console",
+        };
+        Ok(Self {
+            id,
+            topic,
+            name: config.name.clone(),
+            icon,
+            command: config.command.clone(),
+            input: config.input.clone(),
+            user: config.user.clone(),
+            working_directory: config.working_directory.clone(),
+            timeout: Duration::from_secs(config.timeout_in_seconds),
+            job: BackgroundJob::default(),
+            last_run: Arc::default(),
+        })
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Result of the last run, shared with the sensor reporting it.
+    pub fn last_run(&self) -> Arc<LastRun> {
+        self.last_run.clone()
+    }
+
+    /// Build the process to run with the value received.
+    fn build_process(&self, value: &str) -> process::Command {
+        let argv = self
+            .command
+            .iter()
+            .map(|arg| arg.replace("{value}", value))
+            .collect::<Vec<_>>();
+        let mut command = match &self.user {
+            Some(user) => {
+                let mut command = process::Command::new("sudo");
+                command.args(["-n", "-u", user, "--"]).args(&argv);
+                command
+            }
+            None => {
+                let (program, args) = argv.split_first().unwrap();
+                let mut command = process::Command::new(program);
+                command.args(args);
+                command
+            }
+        };
+        if let Some(dir) = &self.working_directory {
+            command.current_dir(dir);
+        }
+        command.stdin(Stdio::null()).kill_on_drop(true);
+        command
+    }
+}
+
+async fn run(mut command: process::Command, timeout_duration: Duration) -> RunResult {
+    let (status, exit_code, stdout, stderr) =
+        match timeout(timeout_duration, command.output()).await {
+            Ok(Ok(output)) => (
+                match output.status.success() {
+                    true => RunStatus::Succeeded,
+                    false => RunStatus::Failed,
+                },
+                output.status.code(),
+                trim_output(&output.stdout),
+                trim_output(&output.stderr),
+            ),
+            Ok(Err(e)) => (RunStatus::Failed, None, String::new(), e.to_string()),
+            Err(_) => (RunStatus::TimedOut, None, String::new(), String::new()),
+        };
+    RunResult {
+        status,
+        exit_code,
+        stdout,
+        stderr,
+        finished_at: OffsetDateTime::now_utc(),
+    }
+}
+
+#[async_trait]
+impl Command for CustomCommand {
+    fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    fn discovery_data(&self) -> Vec<CommandDiscovery<'_>> {
+        vec![
+            CommandDiscovery::new(CommandDiscoveryInit {
+                id: self.id.as_str(),
+                name: self.name.as_str(),
+                icon: self.icon,
+            })
+            .with_input(self.input.clone()),
+        ]
+    }
+
+    async fn execute(&self, payload: &str) -> Result<(), Error> {
+        let command = self.build_process(payload);
+        let id = self.id.clone();
+        let timeout = self.timeout;
+        let last_run = self.last_run.clone();
+        let spawned = self.job.spawn(async move {
+            let result = run(command, timeout).await;
+            let failure = match result.status {
+                RunStatus::Succeeded => None,
+                RunStatus::Failed => Some("failed"),
+                RunStatus::TimedOut => Some("timed out"),
+            };
+            if let Some(failure) = failure {
+                error!("Command {id} {failure}");
+                Metrics::increment(&METRICS.command_failures);
+            }
+            *last_run.result.lock().unwrap() = Some(result);
+            last_run.changed.notify_one();
+        });
+        if !spawned {
+            bail!("Command {} is already running", self.id);
+        }
+        info!("Executing custom command {}", self.id);
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct LastRun {
+    pub result: Mutex<Option<RunResult>>,
+    /// Notified when a new result is available.
+    pub changed: Notify,
+}
+
+#[derive(Clone, Serialize)]
+pub struct RunResult {
+    pub status: RunStatus,
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    #[serde(serialize_with = "serialize_timestamp")]
+    pub finished_at: OffsetDateTime,
+}
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Succeeded,
+    Failed,
+    TimedOut,
+}
+
+/// Trim whitespaces around output and keep only its end if it's too long.
+fn trim_output(output: &[u8]) -> String {
+    let output = String::from_utf8_lossy(output);
+    let output = output.trim();
+    let mut start = output.len().saturating_sub(MAX_OUTPUT_LEN);
+    while !output.is_char_boundary(start) {
+        start += 1;
+    }
+    output[start..].to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CustomCommand;
+    use crate::config;
+
+    fn parse_config(config: &str) -> config::CustomCommand {
+        toml::from_str(config).unwrap()
+    }
+
+    #[test]
+    fn test_value() {
+        let config = parse_config(
+            r#"
+            id = "volume"
+            name = "Volume"
+            command = ["amixer", "set", "Master", "{value}%"]
+            input = { type = "number", min = 0, max = 100, step = 5 }
+            "#,
+        );
+        let command = CustomCommand::new("base", &config).unwrap();
+        let process = command.build_process("25");
+        let process = process.as_std();
+        assert_eq!(process.get_program(), "amixer");
+        let args = process.get_args().collect::<Vec<_>>();
+        assert_eq!(args, ["set", "Master", "25%"]);
+
+        // Free text passed to the command could be taken as options.
+        let config = parse_config(
+            r#"
+            id = "greet"
+            name = "Greet"
+            command = ["echo", "{value}"]
+            input = { type = "text" }
+            "#,
+        );
+        assert!(CustomCommand::new("base", &config).is_err());
+    }
+}
@@ -0,0 +1,147 @@
+use crate::command::{Command, CommandDiscovery, CommandDiscoveryInit, CommandInput};
+use crate::ha::values::EntityCategory;
+use anyhow::{Context, Error, anyhow};
+use async_trait::async_trait;
+use log::info;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::process;
+use tokio::sync::Notify;
+
+const ID: &str = "cpu_governor";
+
+const AVAILABLE_GOVERNORS_FILE: &str =
+    "/sys/devices/system/cpu/cpu0/cpufreq/scaling_available_governors";
+/// Directory of frequency scaling policies, named like `policy0`, as allowed in sudoers.
+const CPUFREQ_DIR: &str = "/sys/devices/system/cpu/cpufreq";
+/// Governors which can be set.
+const ALLOWED_GOVERNORS: [&str; 5] = [
+    "performance",
+    "powersave",
+    "schedutil",
+    "ondemand",
+    "conservative",
+];
+
+/// Sets the CPU frequency scaling governor of all CPUs.
+pub struct GovernorCommand {
+    topic: Box<str>,
+    /// Topic of the CPU frequency sensor, which the select entity takes its state from.
+    state_topic: Box<str>,
+    governors: Vec<String>,
+    changed: Arc<Notify>,
+}
+
+impl GovernorCommand {
+    /// Create the command, or `None` if no allowed governor is available.
+    pub fn new(topic_base: &str, state_topic: &str) -> Option<Self> {
+        let available = fs::read_to_string(AVAILABLE_GOVERNORS_FILE).ok()?;
+        let governors = available
+            .split_ascii_whitespace()
+            .filter(|governor| ALLOWED_GOVERNORS.contains(governor))
+            .map(str::to_owned)
+            .collect::<Vec<_>>();
+        if governors.is_empty() {
+            return None;
+        }
+        Some(Self {
+            topic: format!("{topic_base}/{ID}").into(),
+            state_topic: state_topic.into(),
+            governors,
+            changed: Arc::default(),
+        })
+    }
+
+    /// Notified when the governor is set, for the CPU frequency sensor.
+    pub fn changed(&self) -> Arc<Notify> {
+        self.changed.clone()
+    }
+}
+
+#[async_trait]
+impl Command for GovernorCommand {
+    fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    fn discovery_data(&self) -> Vec<CommandDiscovery<'_>> {
+        vec![
+            CommandDiscovery::new(CommandDiscoveryInit {
+                id: ID,
+                name: "CPU governor",
+                icon: "mdi:speedometer",
+            })
+            .with_entity_category(EntityCategory::Config)
+            .with_input(CommandInput::Select {
+                options: self.governors.clone(),
+            })
+            .with_state(&self.state_topic, "{{ value_json.governor }}"),
+        ]
+    }
+
+    async fn execute(&self, payload: &str) -> Result<(), Error> {
+        if !self.governors.iter().any(|governor| governor == payload) {
+            return Err(anyhow!("Unknown CPU governor {payload}"));
+        }
+        info!("Setting CPU governor to {payload}");
+
+        let result = set_governor(payload).await;
+        self.changed.notify_one();
+        result
+    }
+}
+
+/// Set the governor of all policies.
+async fn set_governor(governor: &str) -> Result<(), Error> {
+    for policy in list_policies()? {
+        write_governor(&policy.join("scaling_governor"), governor).await?;
+    }
+    Ok(())
+}
+
+fn list_policies() -> Result<Vec<PathBuf>, Error> {
+    let mut policies = Vec::new();
+    for entry in fs::read_dir(CPUFREQ_DIR).context("Failed to list cpufreq policies")? {
+        let entry = entry?;
+        let is_policy = entry
+            .file_name()
+            .to_str()
+            .is_some_and(|name| name.starts_with("policy"));
+        if is_policy {
+            policies.push(entry.path());
+        }
+    }
+    Ok(policies)
+}
+
+/// Write the governor via `tee`, as the file is only writable by root.
+async fn write_governor(file: &Path, governor: &str) -> Result<(), Error> {
+    let mut child = process::Command::new("sudo")
+        .args(["-n", "/usr/bin/tee"])
+        .arg(file)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to execute tee")?;
+    let mut stdin = child.stdin.take().context("Failed to open stdin of tee")?;
+    stdin
+        .write_all(governor.as_bytes())
+        .await
+        .context("Failed to write to tee")?;
+    // Close stdin so that tee exits.
+    drop(stdin);
+    let output = child
+        .wait_with_output()
+        .await
+        .context("Failed to wait for tee")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!("Setting CPU governor failed: {}", stderr));
+    }
+    Ok(())
+}
@@ -0,0 +1,51 @@
+use crate::command::{Command, CommandDiscovery, CommandDiscoveryInit};
+use anyhow::{Context, Error, anyhow};
+use async_trait::async_trait;
+use log::info;
+use tokio::process;
+
+const ID: &str = "lock_sessions";
+
+pub struct LockSessionsCommand {
+    topic: Box<str>,
+}
+
+impl LockSessionsCommand {
+    pub fn new(topic_base: &str) -> Self {
+        Self {
+            topic: format!("{topic_base}/{ID}").into(),
+        }
+    }
+}
+
+#[async_trait]
+impl Command for LockSessionsCommand {
+    fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    fn discovery_data(&self) -> Vec<CommandDiscovery<'_>> {
+        vec![CommandDiscovery::new(CommandDiscoveryInit {
+            id: ID,
+            name: "Lock sessions",
+            icon: "mdi:monitor-lock",
+        })]
+    }
+
+    async fn execute(&self, _payload: &str) -> Result<(), Error> {
+        info!("Executing lock sessions command");
+
+        // Locking sessions of other users needs privilege.
+        let output = process::Command::new("sudo")
+            .args(["-n", "/usr/bin/loginctl", "lock-sessions"])
+            .output()
+            .await
+            .context("Failed to execute lock sessions command")?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow!("Lock sessions command failed: {}", stderr));
+        }
+        Ok(())
+    }
+}
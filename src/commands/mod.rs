@@ -1,20 +1,104 @@
+use crate::commands::apt::{AptAction, AptCommand};
+use crate::commands::governor::GovernorCommand;
+use crate::commands::lock_sessions::LockSessionsCommand;
 use crate::commands::reboot::RebootCommand;
+use crate::commands::restart_services::RestartServicesCommand;
+use crate::commands::snap_refresh::SnapRefreshCommand;
 use crate::commands::suspend::SuspendCommand;
+use crate::commands::switch::SwitchCommand;
+use crate::config::Config;
+use crate::sensors;
+use crate::switches::create_switches;
+use crate::utils::snapd;
+use anyhow::{Context as _, Error};
+use std::sync::Arc;
+use zbus::Connection;
 
+pub use self::apt::{AptJobs, JobState, JobStatus};
+pub use self::custom::{CustomCommand, LastRun, RunResult, RunStatus};
+
+mod apt;
+mod background;
+mod custom;
+mod governor;
+mod lock_sessions;
 mod reboot;
+mod restart_services;
+mod snap_refresh;
 mod suspend;
+mod switch;
 
 pub struct Commands {
     pub reboot_command: RebootCommand,
     pub suspend_command: SuspendCommand,
+    pub apt_update_command: AptCommand,
+    pub apt_upgrade_command: AptCommand,
+    pub apt_dist_upgrade_command: AptCommand,
+    pub restart_services_command: RestartServicesCommand,
+    /// Present only if snapd is available.
+    pub snap_refresh_command: Option<SnapRefreshCommand>,
+    /// Present only if desktop is enabled in config.
+    pub lock_sessions_command: Option<LockSessionsCommand>,
+    /// Present only if CPU frequency scaling is available.
+    pub governor_command: Option<GovernorCommand>,
+    pub custom_commands: Vec<CustomCommand>,
+    pub switch_commands: Vec<SwitchCommand>,
 }
 
-pub fn create_commands(topic_base: &str) -> Commands {
+pub fn create_commands(
+    topic_base: &str,
+    config: &Config,
+    connection: &Connection,
+) -> Result<Commands, Error> {
+    let state_topic_base = format!("{topic_base}/switch");
+    // The update entity takes its state from the APT sensor.
+    let apt_topic = sensors::sensor_topic(topic_base, sensors::APT_ID);
+    // The governor select takes its state from the CPU frequency sensor.
+    let cpufreq_topic = sensors::sensor_topic(topic_base, sensors::CPUFREQ_ID);
     let topic_base = format!("{topic_base}/command");
     let reboot_command = RebootCommand::new(&topic_base);
     let suspend_command = SuspendCommand::new(&topic_base);
-    Commands {
+    let apt_jobs = Arc::new(AptJobs::default());
+    let [
+        apt_update_command,
+        apt_upgrade_command,
+        apt_dist_upgrade_command,
+    ] = [
+        AptAction::Update,
+        AptAction::Upgrade,
+        AptAction::DistUpgrade,
+    ]
+    .map(|action| AptCommand::new(&topic_base, &apt_topic, action, apt_jobs.clone()));
+    let restart_services_command = RestartServicesCommand::new(&topic_base);
+    let snap_refresh_command = snapd::is_available().then(|| SnapRefreshCommand::new(&topic_base));
+    let lock_sessions_command = config
+        .desktop
+        .enabled
+        .then(|| LockSessionsCommand::new(&topic_base));
+    let governor_command = GovernorCommand::new(&topic_base, &cpufreq_topic);
+    let custom_commands = config
+        .commands
+        .iter()
+        .map(|config| {
+            CustomCommand::new(&topic_base, config)
+                .with_context(|| format!("Failed to create custom command {}", config.id))
+        })
+        .collect::<Result<_, _>>()?;
+    let switch_commands = create_switches(config, connection)
+        .into_iter()
+        .map(|switch| SwitchCommand::new(&topic_base, &state_topic_base, switch))
+        .collect();
+    Ok(Commands {
         reboot_command,
         suspend_command,
-    }
+        apt_update_command,
+        apt_upgrade_command,
+        apt_dist_upgrade_command,
+        restart_services_command,
+        snap_refresh_command,
+        lock_sessions_command,
+        governor_command,
+        custom_commands,
+        switch_commands,
+    })
 }
@@ -37,7 +37,7 @@ impl Command for RebootCommand {
         ]
     }
 
-    async fn execute(&self) -> Result<(), Error> {
+    async fn execute(&self, _payload: &str) -> Result<(), Error> {
         info!("Executing reboot command");
 
         let output = process::Command::new("sudo")
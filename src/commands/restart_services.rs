@@ -0,0 +1,107 @@
+use super::background::BackgroundJob;
+use crate::command::{Command, CommandDiscovery, CommandDiscoveryInit};
+use crate::ha::values::EntityCategory;
+use crate::metrics::{METRICS, Metrics};
+use crate::utils::needrestart;
+use anyhow::{Context, Error, bail};
+use async_trait::async_trait;
+use log::{error, info};
+use std::sync::Arc;
+use tokio::process;
+use tokio::sync::Notify;
+use tokio::task;
+
+const ID: &str = "restart_services";
+
+/// Restarts services still using deleted binaries or libraries.
+pub struct RestartServicesCommand {
+    topic: Box<str>,
+    job: BackgroundJob,
+    restarted: Arc<Notify>,
+}
+
+impl RestartServicesCommand {
+    pub fn new(topic_base: &str) -> Self {
+        Self {
+            topic: format!("{topic_base}/{ID}").into(),
+            job: BackgroundJob::default(),
+            restarted: Arc::default(),
+        }
+    }
+
+    /// Notified after services are restarted, for the sensor listing them.
+    pub fn restarted(&self) -> Arc<Notify> {
+        self.restarted.clone()
+    }
+}
+
+#[async_trait]
+impl Command for RestartServicesCommand {
+    fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    fn discovery_data(&self) -> Vec<CommandDiscovery<'_>> {
+        vec![
+            CommandDiscovery::new(CommandDiscoveryInit {
+                id: ID,
+                name: "Restart outdated services",
+                icon: "mdi:restart-alert",
+            })
+            .with_entity_category(EntityCategory::Config),
+        ]
+    }
+
+    async fn execute(&self, _payload: &str) -> Result<(), Error> {
+        let restarted = self.restarted.clone();
+        let spawned = self.job.spawn(async move {
+            if let Err(e) = restart_all().await {
+                error!("Failed to restart services: {e}");
+                Metrics::increment(&METRICS.command_failures);
+            }
+            restarted.notify_one();
+        });
+        if !spawned {
+            bail!("Services are already being restarted");
+        }
+        Ok(())
+    }
+}
+
+async fn restart_all() -> Result<(), Error> {
+    let units = task::spawn_blocking(needrestart::find_units_needing_restart)
+        .await?
+        .context("Failed to find services needing restart")?;
+    // Restarting the daemon itself stops it, so leave it to the last.
+    let own_unit = needrestart::own_unit();
+    let (own, mut units): (Vec<_>, Vec<_>) = units
+        .into_iter()
+        .partition(|unit| Some(unit) == own_unit.as_ref());
+    units.extend(own);
+
+    let mut failed = Vec::new();
+    for unit in &units {
+        info!("Restarting {unit}");
+        if let Err(e) = restart(unit).await {
+            error!("Failed to restart {unit}: {e}");
+            failed.push(unit.as_str());
+        }
+    }
+    if !failed.is_empty() {
+        bail!("Failed to restart {}", failed.join(", "));
+    }
+    Ok(())
+}
+
+async fn restart(unit: &str) -> Result<(), Error> {
+    let output = process::Command::new("sudo")
+        // Units are allowed one by one in sudoers, after `--` so they can't be taken as options.
+        .args(["-n", "/usr/bin/systemctl", "restart", "--", unit])
+        .output()
+        .await
+        .context("Failed to execute systemctl restart")?;
+    if !output.status.success() {
+        bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use super::background::BackgroundJob;
+use crate::command::{Command, CommandDiscovery, CommandDiscoveryInit};
+use crate::ha::values::EntityCategory;
+use anyhow::{Context, Error, anyhow, bail};
+use async_trait::async_trait;
+use log::{error, info};
+use std::sync::Arc;
+use tokio::process;
+use tokio::sync::Notify;
+
+const ID: &str = "snap_refresh";
+
+/// Refreshes all snaps in the background.
+pub struct SnapRefreshCommand {
+    topic: Box<str>,
+    job: BackgroundJob,
+    refreshed: Arc<Notify>,
+}
+
+impl SnapRefreshCommand {
+    pub fn new(topic_base: &str) -> Self {
+        Self {
+            topic: format!("{topic_base}/{ID}").into(),
+            job: BackgroundJob::default(),
+            refreshed: Arc::default(),
+        }
+    }
+
+    /// Notified when a refresh finishes, for the snap sensor.
+    pub fn refreshed(&self) -> Arc<Notify> {
+        self.refreshed.clone()
+    }
+}
+
+#[async_trait]
+impl Command for SnapRefreshCommand {
+    fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    fn discovery_data(&self) -> Vec<CommandDiscovery<'_>> {
+        vec![
+            CommandDiscovery::new(CommandDiscoveryInit {
+                id: ID,
+                name: "Refresh snaps",
+                icon: "mdi:package-down",
+            })
+            .with_entity_category(EntityCategory::Config),
+        ]
+    }
+
+    async fn execute(&self, _payload: &str) -> Result<(), Error> {
+        let refreshed = self.refreshed.clone();
+        let spawned = self.job.spawn(async move {
+            if let Err(e) = refresh().await {
+                error!("Failed to refresh snaps: {e}");
+            }
+            refreshed.notify_one();
+        });
+        if !spawned {
+            bail!("Snap refresh is already running");
+        }
+        info!("Executing snap refresh");
+        Ok(())
+    }
+}
+
+async fn refresh() -> Result<(), Error> {
+    let output = process::Command::new("sudo")
+        .args(["-n", "/usr/bin/snap", "refresh"])
+        .output()
+        .await
+        .context("Failed to execute snap refresh")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!("Snap refresh failed: {}", stderr));
+    }
+    Ok(())
+}
@@ -37,7 +37,7 @@ impl Command for SuspendCommand {
         ]
     }
 
-    async fn execute(&self) -> Result<(), Error> {
+    async fn execute(&self, _payload: &str) -> Result<(), Error> {
         info!("Executing suspend command");
 
         let output = process::Command::new("sudo")
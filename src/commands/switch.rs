@@ -0,0 +1,55 @@
+use crate::command::{Command, CommandDiscovery, CommandInput};
+use crate::switch::{Switch, SwitchState};
+use anyhow::Error;
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// Applies the state of a switch received from Home Assistant.
+pub struct SwitchCommand {
+    topic: Box<str>,
+    state: Arc<SwitchState>,
+}
+
+impl SwitchCommand {
+    pub fn new(topic_base: &str, state_topic_base: &str, switch: Box<dyn Switch>) -> Self {
+        let topic = format!("{topic_base}/{}", switch.id()).into_boxed_str();
+        let state_topic = format!("{state_topic_base}/{}", switch.id()).into_boxed_str();
+        Self {
+            topic,
+            state: Arc::new(SwitchState {
+                switch,
+                topic: state_topic,
+                changed: Default::default(),
+            }),
+        }
+    }
+
+    /// State of the switch, shared with the sensor publishing it.
+    pub fn state(&self) -> Arc<SwitchState> {
+        self.state.clone()
+    }
+}
+
+#[async_trait]
+impl Command for SwitchCommand {
+    fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    fn discovery_data(&self) -> Vec<CommandDiscovery<'_>> {
+        vec![
+            self.state
+                .switch
+                .discovery_data()
+                .with_input(CommandInput::Switch)
+                .with_state(&self.state.topic, "{{ 'ON' if value_json.on else 'OFF' }}"),
+        ]
+    }
+
+    async fn execute(&self, payload: &str) -> Result<(), Error> {
+        let result = self.state.switch.apply(payload == "ON").await;
+        // Publish the state even on failure, as it may have been changed partially.
+        self.state.changed.notify_one();
+        result
+    }
+}
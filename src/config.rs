@@ -1,10 +1,36 @@
+use crate::command::CommandInput;
+use crate::ha::values::{EntityCategory, StateClass};
 use serde::Deserialize;
+use std::net::SocketAddr;
 use std::path::PathBuf;
 
 #[derive(Debug, Deserialize)]
 pub struct Config {
     pub daemon: Daemon,
     pub mqtt: Mqtt,
+    /// Serve metrics for Prometheus when present.
+    pub metrics: Option<Metrics>,
+    /// Custom sensors reporting the output of commands.
+    #[serde(default)]
+    pub command_sensors: Vec<CommandSensor>,
+    /// Custom sensors reporting a value read from a file.
+    #[serde(default)]
+    pub file_sensors: Vec<FileSensor>,
+    /// Custom commands exposed as buttons.
+    #[serde(default)]
+    pub commands: Vec<CustomCommand>,
+    /// Built-in controls exposed as switches.
+    #[serde(default)]
+    pub switches: Switches,
+    /// State of desktop sessions.
+    #[serde(default)]
+    pub desktop: Desktop,
+    /// Pressure stall information.
+    #[serde(default)]
+    pub pressure: Pressure,
+    /// CPU usage.
+    #[serde(default)]
+    pub cpu: Cpu,
 }
 
 #[derive(Debug, Deserialize)]
@@ -12,12 +38,19 @@ pub struct Daemon {
     /// Fixed interval in minutes to report status to broker. (Default: 5)
     #[serde(default = "Daemon::default_interval_in_minutes")]
     pub interval_in_minutes: u16,
+    /// Maximum number of sensor readings kept while the broker is unreachable,
+    /// to be replayed once reconnected. Set to 0 to disable. (Default: 1000)
+    #[serde(default = "Daemon::default_offline_buffer_size")]
+    pub offline_buffer_size: usize,
 }
 
 impl Daemon {
     fn default_interval_in_minutes() -> u16 {
         5
     }
+    fn default_offline_buffer_size() -> usize {
+        1000
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -76,3 +109,173 @@ impl Mqtt {
         "home/nodes".to_owned()
     }
 }
+
+#[derive(Debug, Deserialize)]
+pub struct Metrics {
+    /// The address for the HTTP server serving `/metrics` to listen on.
+    pub bind_address: SocketAddr,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CommandSensor {
+    /// Unique ID of the sensor among custom sensors.
+    pub id: String,
+    /// The program and its arguments to run. It is not run through a shell.
+    pub command: Vec<String>,
+    /// Maximum time in seconds to wait for the command to finish. (Default: 10)
+    #[serde(default = "CommandSensor::default_timeout_in_seconds")]
+    pub timeout_in_seconds: u64,
+    /// Minimum interval in minutes between runs of the command.
+    /// The last value is reported in between. (Default: run on every report)
+    pub interval_in_minutes: Option<u16>,
+    /// How to turn the output into the value of the sensor. (Default: string)
+    #[serde(default)]
+    pub parser: CommandParser,
+    /// Path to the value in JSON output, e.g. `$.sensors[0].temp`. (Default: whole output)
+    pub selector: Option<String>,
+    #[serde(flatten)]
+    pub entity: Entity,
+}
+
+impl CommandSensor {
+    fn default_timeout_in_seconds() -> u64 {
+        10
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommandParser {
+    /// The trimmed output parsed as a number.
+    Number,
+    /// The trimmed output as is.
+    #[default]
+    String,
+    /// The output parsed as JSON, with the value picked by `selector`.
+    Json,
+    /// Whether the command exits successfully, as a binary sensor.
+    ExitCode,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FileSensor {
+    /// Unique ID of the sensor among custom sensors.
+    pub id: String,
+    /// Path of the file to read.
+    pub path: PathBuf,
+    /// Regular expression to extract the value from the content,
+    /// using the first capture group if any, or the whole match otherwise.
+    /// (Default: whole content)
+    pub regex: Option<String>,
+    /// How to turn the extracted text into the value of the sensor. (Default: number)
+    #[serde(default)]
+    pub parser: FileParser,
+    /// Factor to multiply a number value by. (Default: 1)
+    #[serde(default = "FileSensor::default_scale")]
+    pub scale: f64,
+    /// Offset to add to a number value after scaling. (Default: 0)
+    #[serde(default)]
+    pub offset: f64,
+    #[serde(flatten)]
+    pub entity: Entity,
+}
+
+impl FileSensor {
+    fn default_scale() -> f64 {
+        1.
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileParser {
+    /// The first field of the text parsed as a number, then scaled and offset.
+    #[default]
+    Number,
+    /// The trimmed text as is.
+    String,
+    /// A number, as a binary sensor which is on when it's non-zero.
+    Binary,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CustomCommand {
+    /// Unique ID of the command among custom commands.
+    pub id: String,
+    /// Name of the button.
+    pub name: String,
+    /// Icon of the button, e.g. `mdi:backup-restore`.
+    pub icon: Option<String>,
+    /// The program and its arguments to run. It is not run through a shell.
+    ///
+    /// Occurrences of `{value}` in the arguments are replaced by the value received,
+    /// which requires a text input to have a pattern.
+    pub command: Vec<String>,
+    /// Value the command takes, which makes it a text, number or select entity instead of a button.
+    #[serde(default)]
+    pub input: CommandInput,
+    /// User to run the command as via `sudo`. (Default: the user of the daemon)
+    pub user: Option<String>,
+    /// Working directory to run the command in. (Default: working directory of the daemon)
+    pub working_directory: Option<PathBuf>,
+    /// Maximum time in seconds to wait for the command to finish. (Default: 60)
+    #[serde(default = "CustomCommand::default_timeout_in_seconds")]
+    pub timeout_in_seconds: u64,
+}
+
+impl CustomCommand {
+    fn default_timeout_in_seconds() -> u64 {
+        60
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Switches {
+    /// Whether to add a switch inhibiting the system from going idle and locking the screen.
+    /// (Default: false)
+    #[serde(default)]
+    pub idle_inhibitor: bool,
+    /// Whether to add a switch for the Wi-Fi radio via NetworkManager. (Default: false)
+    #[serde(default)]
+    pub wifi: bool,
+    /// Systemd units to add switches for enabling and disabling.
+    #[serde(default)]
+    pub systemd_units: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Desktop {
+    /// Whether to report screen lock and idle state of desktop sessions,
+    /// and add a button to lock all sessions. (Default: false)
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Cpu {
+    /// Whether to report the breakdown of CPU time for each CPU,
+    /// besides the breakdown of all CPUs. (Default: false)
+    #[serde(default)]
+    pub per_cpu_breakdown: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Pressure {
+    /// Publish pressure right away when some tasks stall on memory
+    /// for this many milliseconds within 2 seconds. (Default: disabled)
+    pub memory_trigger_in_ms: Option<u16>,
+}
+
+/// Home Assistant metadata of a custom entity.
+#[derive(Debug, Deserialize)]
+pub struct Entity {
+    /// Name of the entity.
+    pub name: String,
+    /// Icon of the entity, e.g. `mdi:thermometer`.
+    pub icon: Option<String>,
+    pub unit_of_measurement: Option<String>,
+    /// Device class, e.g. `temperature`, passed to Home Assistant as is.
+    pub device_class: Option<String>,
+    pub state_class: Option<StateClass>,
+    pub entity_category: Option<EntityCategory>,
+}
@@ -1,7 +1,7 @@
 use crate::command::Command;
 use crate::commands::Commands;
 use crate::ha::discovery::{
-    Device, HaButtonDiscovery, HaComponentDiscovery, HaDeviceDiscovery, HaSensorDiscovery, Origin,
+    Device, HaComponentDiscovery, HaDeviceDiscovery, HaSensorDiscovery, Origin,
 };
 use crate::host::HostInformation;
 use crate::sensor::Sensor;
@@ -22,16 +22,44 @@ pub async fn publish_discovery(
     let Sensors {
         monitor_sensor,
         cpu_sensor,
+        cpufreq_sensor,
         memory_sensor,
+        swap_sensor,
         disk_sensor,
         load_sensor,
+        pressure_sensor,
+        rapl_sensor,
         net_sensor,
+        os_sensor,
+        uptime_sensor,
+        sessions_sensor,
+        desktop_sensor,
         apt_sensor,
+        apt_job_sensor,
+        snap_sensor,
+        unattended_upgrades_sensor,
         reboot_sensor,
+        restart_sensor,
+        resume_sensor,
+        power_state_sensor,
+        custom_command_sensors,
+        custom_file_sensors,
+        custom_command_result_sensors,
+        // Discovered along with their commands.
+        switch_sensors: _,
     } = sensors;
     let Commands {
         reboot_command,
         suspend_command,
+        apt_update_command,
+        apt_upgrade_command,
+        apt_dist_upgrade_command,
+        restart_services_command,
+        snap_refresh_command,
+        lock_sessions_command,
+        governor_command,
+        custom_commands,
+        switch_commands,
     } = commands;
 
     let hostname_snake = make_snake_case(host_info.hostname);
@@ -41,10 +69,12 @@ pub async fn publish_discovery(
     let device = Device {
         name: host_info.hostname,
         identifiers: &[host_info.machine_id],
-        // Send empty string so that if manufacturer or model becomes unavailable,
+        // Send empty string so that if any of these becomes unavailable,
         // it will be cleared correctly in Home Assistant.
         manufacturer: host_info.manufacturer.unwrap_or(""),
         model: host_info.model.unwrap_or(""),
+        sw_version: host_info.os_name.unwrap_or(""),
+        hw_version: host_info.hardware_version.unwrap_or(""),
         connections: &host_info.connections,
     };
     let origin = Origin {
@@ -55,14 +85,66 @@ pub async fn publish_discovery(
         let mut collector = ComponentCollector::new(&hostname_snake);
         collector.add_sensor(monitor_sensor);
         collector.add_sensor(cpu_sensor);
+        if let Some(sensor) = cpufreq_sensor {
+            collector.add_sensor(sensor);
+        }
         collector.add_sensor(memory_sensor);
+        collector.add_sensor(swap_sensor);
         collector.add_sensor(disk_sensor);
         collector.add_sensor(load_sensor);
+        if let Some(sensor) = pressure_sensor {
+            collector.add_sensor(sensor);
+        }
+        if let Some(sensor) = rapl_sensor {
+            collector.add_sensor(sensor);
+        }
         collector.add_sensor(net_sensor);
+        collector.add_sensor(os_sensor);
+        collector.add_sensor(uptime_sensor);
+        collector.add_sensor(sessions_sensor);
+        if let Some(sensor) = desktop_sensor {
+            collector.add_sensor(sensor);
+        }
         collector.add_sensor(apt_sensor);
+        collector.add_sensor(apt_job_sensor);
+        if let Some(sensor) = snap_sensor {
+            collector.add_sensor(sensor);
+        }
+        collector.add_sensor(unattended_upgrades_sensor);
         collector.add_sensor(reboot_sensor);
+        collector.add_sensor(restart_sensor);
+        collector.add_sensor(resume_sensor);
+        collector.add_sensor(power_state_sensor);
+        for sensor in custom_command_sensors {
+            collector.add_sensor(sensor);
+        }
+        for sensor in custom_file_sensors {
+            collector.add_sensor(sensor);
+        }
+        for sensor in custom_command_result_sensors {
+            collector.add_sensor(sensor);
+        }
         collector.add_command(reboot_command);
         collector.add_command(suspend_command);
+        collector.add_command(apt_update_command);
+        collector.add_command(apt_upgrade_command);
+        collector.add_command(apt_dist_upgrade_command);
+        collector.add_command(restart_services_command);
+        if let Some(command) = snap_refresh_command {
+            collector.add_command(command);
+        }
+        if let Some(command) = lock_sessions_command {
+            collector.add_command(command);
+        }
+        if let Some(command) = governor_command {
+            collector.add_command(command);
+        }
+        for command in custom_commands {
+            collector.add_command(command);
+        }
+        for command in switch_commands {
+            collector.add_command(command);
+        }
         collector.result
     };
     let discovery = HaDeviceDiscovery {
@@ -116,11 +198,10 @@ impl<'a> ComponentCollector<'a> {
         self.result
             .extend(command.discovery_data().into_iter().map(|item| {
                 let command_id = format!("{}_command_{}", self.hostname_snake, item.id);
-                let (id, discovery) = HaButtonDiscovery::new(command_id, command.topic(), item);
-                (
-                    format!("button_{}", id),
-                    HaComponentDiscovery::Button(discovery),
-                )
+                let platform = item.input.platform();
+                let (id, discovery) =
+                    HaComponentDiscovery::command(command_id, command.topic(), item);
+                (format!("{platform}_{id}"), discovery)
             }));
     }
 }
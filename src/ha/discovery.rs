@@ -1,6 +1,6 @@
 use std::borrow::Cow;
 
-use crate::command::CommandDiscovery;
+use crate::command::{CommandDiscovery, CommandInput};
 use crate::ha::values::{DeviceClass, EntityCategory, StateClass};
 use crate::sensor::SensorDiscovery;
 use crate::utils::serialize::serialize_as_map;
@@ -22,6 +22,8 @@ pub struct Device<'a> {
     pub identifiers: &'a [&'static str],
     pub manufacturer: &'static str,
     pub model: &'static str,
+    pub sw_version: &'static str,
+    pub hw_version: &'static str,
     #[serde(skip_serializing_if = "<[_]>::is_empty")]
     pub connections: &'a [(&'static str, String)],
 }
@@ -37,7 +39,12 @@ pub struct Origin {
 pub enum HaComponentDiscovery<'a> {
     Sensor(HaSensorDiscovery<'a>),
     BinarySensor(HaSensorDiscovery<'a>),
-    Button(HaButtonDiscovery<'a>),
+    Button(HaCommandDiscovery<'a>),
+    Text(HaCommandDiscovery<'a>),
+    Number(HaCommandDiscovery<'a>),
+    Select(HaCommandDiscovery<'a>),
+    Switch(HaCommandDiscovery<'a>),
+    Update(HaCommandDiscovery<'a>),
 }
 
 #[derive(Serialize)]
@@ -60,6 +67,10 @@ pub struct HaSensorDiscovery<'a> {
     state_topic: &'a str,
     value_template: Cow<'a, str>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    availability_topic: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    availability_template: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     json_attributes_topic: Option<&'a str>,
     #[serde(skip_serializing_if = "Option::is_none")]
     json_attributes_template: Option<&'static str>,
@@ -75,6 +86,12 @@ impl<'a> HaSensorDiscovery<'a> {
             Some(tpl) => (Some(topic), Some(tpl)),
             None => (None, None),
         };
+        // Take any message on its own state topic as a sign of availability,
+        // which overrides the availability of the device.
+        let (availability_topic, availability_tpl) = match discovery.always_available {
+            true => (Some(topic), Some("online")),
+            false => (None, None),
+        };
         (
             discovery.id,
             HaSensorDiscovery {
@@ -88,6 +105,8 @@ impl<'a> HaSensorDiscovery<'a> {
                 suggested_display_precision: discovery.suggested_display_precision,
                 state_topic: topic,
                 value_template: discovery.value_template,
+                availability_topic,
+                availability_template: availability_tpl,
                 json_attributes_topic: attrs_topic,
                 json_attributes_template: attrs_tpl,
             },
@@ -104,7 +123,7 @@ bitflags! {
 }
 
 #[derive(Serialize)]
-pub struct HaButtonDiscovery<'a> {
+pub struct HaCommandDiscovery<'a> {
     unique_id: String,
     name: Cow<'a, str>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -114,24 +133,76 @@ pub struct HaButtonDiscovery<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
     device_class: Option<&'static str>,
     command_topic: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    state_topic: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value_template: Option<&'static str>,
+    #[serde(flatten)]
+    input: HaCommandInput,
 }
 
-impl<'a> HaButtonDiscovery<'a> {
-    pub fn new(
+#[derive(Serialize)]
+#[serde(untagged)]
+enum HaCommandInput {
+    Button {},
+    Text {
+        min: usize,
+        max: usize,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pattern: Option<String>,
+    },
+    Number {
+        min: f64,
+        max: f64,
+        step: f64,
+    },
+    Select {
+        options: Vec<String>,
+    },
+    Switch {},
+    Update {
+        payload_install: &'static str,
+    },
+}
+
+impl<'a> HaComponentDiscovery<'a> {
+    /// Discovery of a command as the entity matching its input.
+    pub fn command(
         unique_id: String,
         command_topic: &'a str,
         discovery: CommandDiscovery<'a>,
     ) -> (Cow<'a, str>, Self) {
+        let input = match discovery.input {
+            CommandInput::Button => HaCommandInput::Button {},
+            CommandInput::Text { min, max, pattern } => HaCommandInput::Text { min, max, pattern },
+            CommandInput::Number { min, max, step } => HaCommandInput::Number { min, max, step },
+            CommandInput::Select { options } => HaCommandInput::Select { options },
+            CommandInput::Switch => HaCommandInput::Switch {},
+            CommandInput::Update => HaCommandInput::Update {
+                payload_install: "install",
+            },
+        };
+        let platform = match input {
+            HaCommandInput::Button {} => HaComponentDiscovery::Button,
+            HaCommandInput::Text { .. } => HaComponentDiscovery::Text,
+            HaCommandInput::Number { .. } => HaComponentDiscovery::Number,
+            HaCommandInput::Select { .. } => HaComponentDiscovery::Select,
+            HaCommandInput::Switch {} => HaComponentDiscovery::Switch,
+            HaCommandInput::Update { .. } => HaComponentDiscovery::Update,
+        };
         (
             discovery.id,
-            HaButtonDiscovery {
+            platform(HaCommandDiscovery {
                 unique_id,
                 name: discovery.name,
                 icon: discovery.icon,
                 entity_category: discovery.entity_category,
                 device_class: discovery.device_class,
                 command_topic,
-            },
+                state_topic: discovery.state_topic,
+                value_template: discovery.value_template,
+                input,
+            }),
         )
     }
 }
@@ -1,23 +1,38 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 /// Device class is a measurement categorization in Home Assistant.
 ///
-/// See https://www.home-assistant.io/integrations/sensor/#device-class.
-#[derive(Clone, Copy, Serialize)]
+/// See https://www.home-assistant.io/integrations/sensor/#device-class
+/// and https://www.home-assistant.io/integrations/binary_sensor/#device-class.
+#[derive(Clone, Copy, Debug, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum DeviceClass {
+    // Sensor device classes.
     DataRate,
     DataSize,
+    Date,
+    Duration,
+    Energy,
+    Frequency,
+    Power,
     Timestamp,
+    // Binary sensor device classes.
+    Problem,
+    Update,
+    /// Any other device class, e.g. given in config for custom sensors.
+    #[serde(untagged)]
+    Other(&'static str),
 }
 
-#[derive(Clone, Copy, Serialize)]
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum StateClass {
     Measurement,
+    Total,
+    TotalIncreasing,
 }
 
-#[derive(Clone, Copy, Serialize)]
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum EntityCategory {
     Config,
@@ -3,6 +3,7 @@ use std::fs;
 pub struct Dmi {
     pub manufacturer: Option<String>,
     pub model: Option<String>,
+    pub version: Option<String>,
 }
 
 pub fn get_dmi() -> Dmi {
@@ -14,8 +15,13 @@ pub fn get_dmi() -> Dmi {
         .ok()
         .map(|s| s.trim().to_string())
         .filter(|s| !s.is_empty() && s != "System Product Name");
+    let version = fs::read_to_string("/sys/class/dmi/id/product_version")
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty() && s != "System Version" && s != "To be filled by O.E.M.");
     Dmi {
         manufacturer,
         model,
+        version,
     }
 }
@@ -1,14 +1,18 @@
 use anyhow::{Context as _, Result};
+use log::warn;
 
 mod connections;
 mod dmi;
 mod machine_id;
+pub mod os_release;
 
 pub struct HostInformation {
     pub hostname: &'static str,
     pub machine_id: &'static str,
     pub manufacturer: Option<&'static str>,
     pub model: Option<&'static str>,
+    pub hardware_version: Option<&'static str>,
+    pub os_name: Option<&'static str>,
     pub connections: Vec<(&'static str, String)>,
 }
 
@@ -20,11 +24,17 @@ impl HostInformation {
         let machine_id: &str = machine_id.hyphenated().to_string().leak();
         let dmi = dmi::get_dmi();
         let connections = connections::get_connections().context("Failed to get connections")?;
+        let os_name = os_release::get()
+            .inspect_err(|e| warn!("Failed to read OS release: {e}"))
+            .ok()
+            .and_then(|os_release| os_release.pretty_name);
         Ok(Self {
             hostname,
             machine_id,
             manufacturer: dmi.manufacturer.map(|s| s.leak() as &'static str),
             model: dmi.model.map(|s| s.leak() as &'static str),
+            hardware_version: dmi.version.map(|s| s.leak() as &'static str),
+            os_name: os_name.map(|s| s.leak() as &'static str),
             connections,
         })
     }
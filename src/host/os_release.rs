@@ -0,0 +1,57 @@
+use anyhow::{Context, Error};
+use std::fs;
+
+const OS_RELEASE_FILE: &str = "/etc/os-release";
+
+pub struct OsRelease {
+    pub id: Option<String>,
+    pub pretty_name: Option<String>,
+    pub version_id: Option<String>,
+    pub codename: Option<String>,
+}
+
+pub fn get() -> Result<OsRelease, Error> {
+    let content = fs::read_to_string(OS_RELEASE_FILE).context("Failed to read os-release file")?;
+    Ok(parse_os_release(&content))
+}
+
+fn parse_os_release(content: &str) -> OsRelease {
+    let mut result = OsRelease {
+        id: None,
+        pretty_name: None,
+        version_id: None,
+        codename: None,
+    };
+    for line in content.lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim().trim_matches('"').to_owned();
+        let field = match key.trim() {
+            "ID" => &mut result.id,
+            "PRETTY_NAME" => &mut result.pretty_name,
+            "VERSION_ID" => &mut result.version_id,
+            "VERSION_CODENAME" => &mut result.codename,
+            _ => continue,
+        };
+        *field = Some(value);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_os_release;
+
+    #[test]
+    fn test_parse_os_release() {
+        let os_release = parse_os_release(include_str!("assets/os_release_1"));
+        assert_eq!(os_release.id.as_deref(), Some("ubuntu"));
+        assert_eq!(
+            os_release.pretty_name.as_deref(),
+            Some("Ubuntu 24.04.1 LTS")
+        );
+        assert_eq!(os_release.version_id.as_deref(), Some("24.04"));
+        assert_eq!(os_release.codename.as_deref(), Some("noble"));
+    }
+}
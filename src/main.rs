@@ -1,6 +1,6 @@
 use crate::config::Config;
 use crate::host::HostInformation;
-use crate::main_loop::StopReason;
+use crate::main_loop::{StartReason, StopReason};
 use crate::sleep_monitor::SleepEvent;
 use anyhow::{Context as _, Error, Result, anyhow};
 use futures_util::{Stream, TryStreamExt as _, pin_mut};
@@ -21,10 +21,15 @@ mod discovery_publisher;
 mod ha;
 mod host;
 mod main_loop;
+mod metrics;
+mod offline_buffer;
 mod sensor;
 mod sensor_publisher;
 mod sensors;
 mod sleep_monitor;
+mod switch;
+mod switches;
+mod systemd;
 mod utils;
 
 #[global_allocator]
@@ -43,6 +48,10 @@ async fn main() -> Result<(), Error> {
     let config = toml::from_str::<Config>(&config).context("Could not parse config.toml")?;
     trace!("Config: {:#?}", config);
 
+    if let Some(metrics) = &config.metrics {
+        metrics::start_server(metrics.bind_address).await?;
+    }
+
     let mut signals =
         Signals::new([SIGINT, SIGTERM]).context("Failed to initialize signal handler")?;
     static SHUTDOWN: SetOnce<()> = SetOnce::const_new();
@@ -58,15 +67,23 @@ async fn main() -> Result<(), Error> {
     let sleep_events = sleep_monitor.start_monitoring().await?;
     pin_mut!(sleep_events);
 
-    let main_loop = main_loop::MainLoop::new(host_info, config)?;
+    let main_loop = main_loop::MainLoop::new(host_info, config, sleep_monitor.connection())?;
+    // Report ready before connecting, as connecting retries indefinitely
+    // while readings are buffered, which would otherwise time out the start.
+    systemd::notify_ready();
+    let mut start = StartReason::Startup;
     loop {
         let stop = async {
+            let events = [SleepEvent::PreparingSleep, SleepEvent::PreparingShutdown];
             select! {
-                _ = wait_for_sleep_event(&mut sleep_events, SleepEvent::PreparingSleep) => StopReason::Sleep,
+                e = wait_for_sleep_event(&mut sleep_events, &events) => match e {
+                    Ok(SleepEvent::PreparingShutdown) => StopReason::PowerOff,
+                    _ => StopReason::Sleep,
+                },
                 _ = SHUTDOWN.wait() => StopReason::Shutdown,
             }
         };
-        {
+        let reason = {
             let _inhibitor_lock = match sleep_monitor.take_inhibitor_lock().await {
                 Ok(lock) => Some(lock),
                 Err(e) => {
@@ -74,26 +91,41 @@ async fn main() -> Result<(), Error> {
                     None
                 }
             };
-            main_loop.run(stop).await?;
-        }
+            main_loop.run(start, stop).await?
+        };
         if SHUTDOWN.get().is_some() {
             break;
         }
-        wait_for_sleep_event(&mut sleep_events, SleepEvent::WakingUp).await?;
+        match reason {
+            StopReason::Shutdown => break,
+            StopReason::Sleep => {
+                wait_for_sleep_event(&mut sleep_events, &[SleepEvent::WakingUp]).await?;
+                start = StartReason::Resume;
+            }
+            StopReason::PowerOff => {
+                let events = [SleepEvent::ShutdownCancelled];
+                select! {
+                    _ = SHUTDOWN.wait() => break,
+                    r = wait_for_sleep_event(&mut sleep_events, &events) => r?,
+                };
+                info!("Shutdown cancelled");
+                start = StartReason::Reconnect;
+            }
+        }
     }
     Ok(())
 }
 
 async fn wait_for_sleep_event<S>(
     sleep_events: &mut Pin<&mut S>,
-    event: SleepEvent,
-) -> Result<(), Error>
+    events: &[SleepEvent],
+) -> Result<SleepEvent, Error>
 where
     S: Stream<Item = Result<SleepEvent>>,
 {
     while let Some(e) = sleep_events.try_next().await? {
-        if e == event {
-            return Ok(());
+        if events.contains(&e) {
+            return Ok(e);
         }
     }
     Err(anyhow!("Unexpected end of sleep events stream"))
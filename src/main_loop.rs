@@ -1,20 +1,26 @@
 use crate::commands::create_commands;
 use crate::config::{Config, Mqtt};
+use crate::discovery_publisher;
 use crate::host::HostInformation;
-use crate::sensors::{Sensors, create_sensors};
+use crate::metrics::{METRICS, Metrics};
+use crate::offline_buffer::OfflineBuffer;
+use crate::sensor_publisher::SensorPublisher;
+use crate::sensors::{PowerState, Sensors, create_sensors};
+use crate::systemd::{self, Watchdog};
 use crate::utils::snake_case::make_snake_case;
 use crate::{command_subscriber, commands::Commands};
-use crate::{discovery_publisher, sensor_publisher};
 use anyhow::{Context as _, Error, Result, anyhow};
 use backoff::ExponentialBackoff;
 use futures_util::{FutureExt as _, TryFutureExt as _};
 use log::{debug, info, warn};
 use rumqttc::{AsyncClient, Event, Incoming, MqttOptions, Outgoing, QoS};
+use std::sync::Mutex;
 use std::time::Duration;
 use std::{fmt, fs};
 use tokio::select;
 use tokio::sync::mpsc::{self, error::TrySendError};
-use tokio::time::{MissedTickBehavior, interval, sleep, timeout};
+use tokio::time::{Instant, MissedTickBehavior, interval, interval_at, sleep, timeout};
+use zbus::Connection;
 
 pub struct MainLoop {
     host_info: HostInformation,
@@ -23,19 +29,30 @@ pub struct MainLoop {
     commands: Commands,
     availability_topic: String,
     options: MqttOptions,
+    offline_buffer: OfflineBuffer,
+    /// When the next periodic publish is due, so buffering can continue on schedule.
+    next_tick: Mutex<Instant>,
 }
 
 impl MainLoop {
-    pub fn new(host_info: HostInformation, config: Config) -> Result<Self> {
+    pub fn new(
+        host_info: HostInformation,
+        config: Config,
+        connection: &Connection,
+    ) -> Result<Self> {
         let topic_base = format!(
             "{}/{}",
             config.mqtt.base_topic,
             make_snake_case(host_info.hostname)
         );
-        let sensors = create_sensors(&topic_base)?;
-        let commands = create_commands(&topic_base);
+        METRICS.set_topic_base(&topic_base);
+        let commands = create_commands(&topic_base, &config, connection)?;
+        let sensors = create_sensors(&topic_base, &config, &commands, connection)?;
         let availability_topic = format!("{topic_base}/availability");
         let options = build_mqtt_options(host_info.hostname, &config.mqtt)?;
+        let offline_buffer = OfflineBuffer::new(config.daemon.offline_buffer_size);
+        let interval = Duration::from_secs(u64::from(config.daemon.interval_in_minutes) * 60);
+        let next_tick = Mutex::new(Instant::now() + interval);
         Ok(Self {
             host_info,
             config,
@@ -43,14 +60,22 @@ impl MainLoop {
             commands,
             availability_topic,
             options,
+            offline_buffer,
+            next_tick,
         })
     }
 
     async fn initialize(
         &self,
+        start: StartReason,
         stop: impl Future<Output = StopReason>,
     ) -> Result<impl Future<Output = Result<()>>> {
-        let backoff = ExponentialBackoff::default();
+        // Keep retrying indefinitely, as readings are buffered in the meantime.
+        let backoff = ExponentialBackoff {
+            max_elapsed_time: None,
+            ..Default::default()
+        };
+        systemd::notify_status("Connecting to MQTT broker...");
         let (client, mut event_loop) = backoff::future::retry(backoff, || async {
             let (client, mut event_loop) = AsyncClient::new(self.options.clone(), 10);
             loop {
@@ -59,6 +84,7 @@ impl MainLoop {
                     Ok(_) => {}
                     Err(e) => {
                         warn!("Failed to connect to MQTT: {}", e);
+                        systemd::notify_status(&format!("Failed to connect to MQTT broker: {e}"));
                         break Err(backoff::Error::transient(e));
                     }
                 }
@@ -74,7 +100,7 @@ impl MainLoop {
                     .context("Failed to poll event loop")?;
                 match event {
                     Event::Incoming(Incoming::Publish(publish)) => {
-                        match msg_sender.try_send(publish.topic) {
+                        match msg_sender.try_send((publish.topic, publish.payload)) {
                             Ok(_) => {}
                             Err(TrySendError::Full(_)) => {
                                 warn!("Dropping message due to full channel");
@@ -102,11 +128,11 @@ impl MainLoop {
             .context("Failed to subscribe to commands")?;
         let handling_commands = async move {
             loop {
-                let topic = msg_receiver
+                let (topic, payload) = msg_receiver
                     .recv()
                     .await
                     .context("Failed to receive message")?;
-                command_subscriber.handle_message(&topic).await;
+                command_subscriber.handle_message(&topic, &payload).await;
             }
             #[allow(unreachable_code)]
             Ok::<_, Error>(())
@@ -123,31 +149,26 @@ impl MainLoop {
         )
         .await
         .context("Failed to publish discovery")?;
+        systemd::notify_status("Connected to MQTT broker");
         // Wait for a few seconds before publishing the first status.
-        sleep(Duration::from_secs(5)).await;
+        // Discovery is retained and unchanged after resuming, so no need to wait then.
+        if let StartReason::Startup = start {
+            sleep(Duration::from_secs(5)).await;
+        }
 
         let publishing = {
             let client = client.clone();
             async move {
-                let publisher = sensor_publisher::SensorPublisher {
-                    client: &client,
+                let publisher = SensorPublisher {
+                    client: Some(&client),
                     sensors: &self.sensors,
+                    offline_buffer: &self.offline_buffer,
                 };
-                let interval_duration =
-                    Duration::from_secs(u64::from(self.config.daemon.interval_in_minutes) * 60);
-                let mut interval = interval(interval_duration);
-                interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
-                // Don't let publishing breach 80% of interval.
-                let timeout_duration = interval_duration * 4 / 5;
-
-                loop {
-                    interval.tick().await;
-                    match timeout(timeout_duration, publisher.publish_status()).await {
-                        Ok(()) => {}
-                        // Ignore timeout.
-                        Err(_) => warn!("Timeout publishing"),
-                    }
-                }
+                publisher.replay_buffered().await;
+                tokio::join!(
+                    self.publish_periodically(&publisher, Instant::now()),
+                    publisher.publish_on_trigger(),
+                );
             }
         };
 
@@ -168,10 +189,22 @@ impl MainLoop {
                         }
                     }
                 };
-                select! {
+                let reason = select! {
                     e = publishing_online => return Err(e.context("Failed to publish online")),
-                    reason = stop => info!("Stopping for {}...", reason),
-                }
+                    reason = stop => reason,
+                };
+                info!("Stopping for {}...", reason);
+                systemd::notify_status(&format!("Stopped for {reason}"));
+                debug!("Sending power state");
+                let publisher = SensorPublisher {
+                    client: Some(&client),
+                    sensors: &self.sensors,
+                    offline_buffer: &self.offline_buffer,
+                };
+                self.sensors.power_state_sensor.set(reason.into());
+                publisher
+                    .publish_payload(&self.sensors.power_state_sensor)
+                    .await;
                 debug!("Sending offline message");
                 client
                     .publish(&self.availability_topic, QoS::AtLeastOnce, false, "offline")
@@ -193,16 +226,90 @@ impl MainLoop {
         })
     }
 
-    pub async fn run(&self, stop: impl Future<Output = StopReason>) -> Result<()> {
-        let stop = stop.shared();
-        let r = select! {
-            r = self.initialize(stop.clone()) => r?,
-            reason = stop => {
-                info!("Stopping during initialization for {}...", reason);
-                return Ok(());
+    /// Keep reading sensors into the offline buffer while not connected to the broker.
+    async fn buffer_readings(&self) {
+        let publisher = SensorPublisher {
+            client: None,
+            sensors: &self.sensors,
+            offline_buffer: &self.offline_buffer,
+        };
+        // Start buffering from the first tick missed while disconnected.
+        let start = *self.next_tick.lock().unwrap();
+        self.publish_periodically(&publisher, start).await;
+    }
+
+    async fn publish_periodically(&self, publisher: &SensorPublisher<'_>, start: Instant) {
+        let interval_duration = self.interval_duration();
+        let mut interval = interval_at(start, interval_duration);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        // Don't let publishing breach 80% of interval.
+        let timeout_duration = interval_duration * 4 / 5;
+        let publishing = async {
+            loop {
+                let tick = interval.tick().await;
+                *self.next_tick.lock().unwrap() = tick + interval_duration;
+                match timeout(timeout_duration, publisher.publish_status()).await {
+                    Ok(()) => {}
+                    // Ignore timeout.
+                    Err(_) => warn!("Timeout publishing"),
+                }
+            }
+        };
+        // Ping the watchdog alongside, rather than between publishes,
+        // as a slow publish may take longer than the watchdog timeout with a long interval.
+        // Publishing is bounded by the timeout above anyway.
+        let mut watchdog = Watchdog::new();
+        let pinging = async {
+            loop {
+                watchdog.ping().await;
             }
         };
-        r.await
+        select! {
+            () = publishing => {}
+            () = pinging => {}
+        }
+    }
+
+    fn interval_duration(&self) -> Duration {
+        Duration::from_secs(u64::from(self.config.daemon.interval_in_minutes) * 60)
+    }
+
+    pub async fn run(
+        &self,
+        start: StartReason,
+        stop: impl Future<Output = StopReason>,
+    ) -> Result<StopReason> {
+        if let StartReason::Resume = start {
+            self.sensors.prepare_resume();
+        }
+        self.sensors.power_state_sensor.set(PowerState::Running);
+        let stop = stop.shared();
+        let mut start = start;
+        loop {
+            let r = select! {
+                r = self.initialize(start, stop.clone()) => r,
+                () = self.buffer_readings() => unreachable!("Buffering should never complete"),
+                reason = stop.clone() => {
+                    info!("Stopping during initialization for {}...", reason);
+                    systemd::notify_status(&format!("Stopped for {reason}"));
+                    return Ok(reason);
+                }
+            };
+            // Failing to initialize, e.g. to subscribe, is handled like losing connection.
+            let r = match r {
+                Ok(running) => running.await,
+                Err(e) => Err(e),
+            };
+            match r {
+                Ok(()) => return Ok(stop.await),
+                Err(e) => {
+                    warn!("Lost connection to MQTT, reconnecting: {e:#}");
+                    Metrics::increment(&METRICS.reconnects);
+                    systemd::notify_status("Lost connection to MQTT broker, buffering readings");
+                }
+            }
+            start = StartReason::Reconnect;
+        }
     }
 }
 
@@ -229,10 +336,18 @@ fn build_mqtt_options(hostname: &str, config: &Mqtt) -> Result<MqttOptions, Erro
     Ok(options)
 }
 
+#[derive(Clone, Copy)]
+pub enum StartReason {
+    Startup,
+    Resume,
+    Reconnect,
+}
+
 #[derive(Clone, Copy)]
 pub enum StopReason {
     Shutdown,
     Sleep,
+    PowerOff,
 }
 
 impl fmt::Display for StopReason {
@@ -240,6 +355,7 @@ impl fmt::Display for StopReason {
         match self {
             StopReason::Shutdown => write!(f, "shutting down"),
             StopReason::Sleep => write!(f, "sleeping"),
+            StopReason::PowerOff => write!(f, "powering off"),
         }
     }
 }
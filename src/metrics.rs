@@ -0,0 +1,228 @@
+use crate::utils::snake_case::make_snake_case;
+use anyhow::{Context as _, Error};
+use log::{debug, info};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt as _, AsyncWriteExt as _, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::timeout;
+
+const PREFIX: &str = "ubuntu_mqtt2ha";
+
+pub static METRICS: Metrics = Metrics::new();
+
+/// Values collected by sensors and internal counters, exposed in Prometheus format.
+pub struct Metrics {
+    /// Base of topics of this host, which readings are keyed relative to.
+    topic_base: OnceLock<String>,
+    /// Latest reading of each sensor, keyed by its topic relative to the topic base,
+    /// e.g. `sensor/cpu` or `switch/wifi`.
+    readings: Mutex<BTreeMap<String, Value>>,
+    pub publish_failures: AtomicU64,
+    pub reconnects: AtomicU64,
+    pub command_executions: AtomicU64,
+    pub command_failures: AtomicU64,
+}
+
+impl Metrics {
+    const fn new() -> Self {
+        Self {
+            topic_base: OnceLock::new(),
+            readings: Mutex::new(BTreeMap::new()),
+            publish_failures: AtomicU64::new(0),
+            reconnects: AtomicU64::new(0),
+            command_executions: AtomicU64::new(0),
+            command_failures: AtomicU64::new(0),
+        }
+    }
+
+    pub fn increment(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn set_topic_base(&self, topic_base: &str) {
+        self.topic_base.get_or_init(|| topic_base.to_owned());
+    }
+
+    pub fn record_reading(&self, topic: &str, value: Value) {
+        let key = self
+            .topic_base
+            .get()
+            .and_then(|topic_base| topic.strip_prefix(topic_base.as_str()))
+            .and_then(|topic| topic.strip_prefix('/'))
+            .unwrap_or(topic);
+        self.readings.lock().unwrap().insert(key.to_owned(), value);
+    }
+
+    fn render(&self) -> String {
+        let mut output = String::new();
+        let counters = [
+            ("publish_failures_total", &self.publish_failures),
+            ("reconnects_total", &self.reconnects),
+            ("command_executions_total", &self.command_executions),
+            ("command_failures_total", &self.command_failures),
+        ];
+        for (name, counter) in counters {
+            let value = counter.load(Ordering::Relaxed);
+            writeln!(output, "# TYPE {PREFIX}_{name} counter").unwrap();
+            writeln!(output, "{PREFIX}_{name} {value}").unwrap();
+        }
+        for (key, value) in self.readings.lock().unwrap().iter() {
+            let name = format!("{PREFIX}_{}", make_snake_case(key));
+            render_value(&mut output, &name, value);
+        }
+        output
+    }
+}
+
+/// Flatten a reading into gauges.
+///
+/// Object keys are appended to the metric name,
+/// and arrays of numbers, e.g. per-CPU usage, become series with an `index` label.
+/// Other values, e.g. strings and lists of packages, are skipped.
+fn render_value(output: &mut String, name: &str, value: &Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let name = format!("{name}_{}", make_snake_case(key));
+                render_value(output, &name, value);
+            }
+        }
+        Value::Array(list) => {
+            let Some(numbers) = list.iter().map(as_number).collect::<Option<Vec<_>>>() else {
+                return;
+            };
+            if numbers.is_empty() {
+                return;
+            }
+            writeln!(output, "# TYPE {name} gauge").unwrap();
+            for (i, number) in numbers.iter().enumerate() {
+                writeln!(output, "{name}{{index=\"{i}\"}} {number}").unwrap();
+            }
+        }
+        _ => {
+            if let Some(number) = as_number(value) {
+                writeln!(output, "# TYPE {name} gauge").unwrap();
+                writeln!(output, "{name} {number}").unwrap();
+            }
+        }
+    }
+}
+
+fn as_number(value: &Value) -> Option<String> {
+    match value {
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(u8::from(*b).to_string()),
+        _ => None,
+    }
+}
+
+pub async fn start_server(bind_address: SocketAddr) -> Result<(), Error> {
+    let listener = TcpListener::bind(bind_address)
+        .await
+        .with_context(|| format!("Failed to bind metrics server to {bind_address}"))?;
+    info!("Serving metrics on http://{bind_address}/metrics");
+    tokio::spawn(async move {
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(conn) => conn,
+                Err(e) => {
+                    debug!("Failed to accept metrics connection: {e}");
+                    continue;
+                }
+            };
+            tokio::spawn(async move {
+                if let Err(e) = handle_connection(stream).await {
+                    debug!("Failed to serve metrics to {peer}: {e}");
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+async fn handle_connection(mut stream: TcpStream) -> Result<(), Error> {
+    let (reader, mut writer) = stream.split();
+    let mut lines = BufReader::new(reader).lines();
+    let request_line = timeout(Duration::from_secs(10), async {
+        let request_line = lines.next_line().await?;
+        // Skip the headers.
+        while let Some(line) = lines.next_line().await? {
+            if line.is_empty() {
+                break;
+            }
+        }
+        Ok::<_, Error>(request_line)
+    })
+    .await
+    .context("Timeout reading request")??
+    .context("Empty request")?;
+
+    let mut parts = request_line.split_ascii_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => ("200 OK", METRICS.render()),
+        (Some("GET"), _) => ("404 Not Found", "Not found\n".to_owned()),
+        _ => ("405 Method Not Allowed", "Method not allowed\n".to_owned()),
+    };
+    let response = format!(
+        "HTTP/1.1 {status}\r\n\
+         Content-Type: text/plain; version=0.0.4\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\
+         \r\n\
+         {body}",
+        body.len(),
+    );
+    writer.write_all(response.as_bytes()).await?;
+    writer.shutdown().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Metrics, render_value};
+    use serde_json::json;
+
+    #[test]
+    fn test_render_value() {
+        let value = json!({
+            "total": 12.5,
+            "per_cpu": [1.5, 2],
+            "eth0": { "bytes_in": 100 },
+            "state": true,
+            "name": "ignored",
+            "packages": [{ "name": "ignored", "size": 1 }],
+            "empty": [],
+        });
+        let mut output = String::new();
+        render_value(&mut output, "cpu", &value);
+        assert_eq!(
+            output,
+            "# TYPE cpu_total gauge\n\
+             cpu_total 12.5\n\
+             # TYPE cpu_per_cpu gauge\n\
+             cpu_per_cpu{index=\"0\"} 1.5\n\
+             cpu_per_cpu{index=\"1\"} 2\n\
+             # TYPE cpu_eth0_bytes_in gauge\n\
+             cpu_eth0_bytes_in 100\n\
+             # TYPE cpu_state gauge\n\
+             cpu_state 1\n",
+        );
+    }
+
+    #[test]
+    fn test_record_reading() {
+        let metrics = Metrics::new();
+        metrics.set_topic_base("home/nodes/host");
+        metrics.record_reading("home/nodes/host/sensor/wifi", json!({ "state": 1 }));
+        metrics.record_reading("home/nodes/host/switch/wifi", json!({ "state": 0 }));
+        let output = metrics.render();
+        assert!(output.contains("\nubuntu_mqtt2ha_sensor_wifi_state 1\n"));
+        assert!(output.contains("\nubuntu_mqtt2ha_switch_wifi_state 0\n"));
+    }
+}
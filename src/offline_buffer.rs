@@ -0,0 +1,86 @@
+use crate::utils::serialize::serialize_timestamp;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::mem;
+use std::sync::Mutex;
+use time::OffsetDateTime;
+
+/// Bounded buffer of sensor readings taken while the broker is unreachable.
+///
+/// Once full, the oldest readings are dropped to make room for new ones.
+pub struct OfflineBuffer {
+    capacity: usize,
+    readings: Mutex<VecDeque<BufferedReading>>,
+}
+
+pub struct BufferedReading {
+    pub topic: String,
+    pub message: BufferedMessage,
+}
+
+#[derive(Serialize)]
+pub struct BufferedMessage {
+    #[serde(serialize_with = "serialize_timestamp")]
+    timestamp: OffsetDateTime,
+    payload: Value,
+}
+
+impl OfflineBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            readings: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn push(&self, topic: &str, payload: Value) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut readings = self.readings.lock().unwrap();
+        if readings.len() >= self.capacity {
+            readings.pop_front();
+        }
+        readings.push_back(BufferedReading {
+            topic: topic.to_owned(),
+            message: BufferedMessage {
+                timestamp: OffsetDateTime::now_utc(),
+                payload,
+            },
+        });
+    }
+
+    /// Take all buffered readings out, oldest first.
+    pub fn take(&self) -> VecDeque<BufferedReading> {
+        mem::take(&mut *self.readings.lock().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OfflineBuffer;
+    use serde_json::json;
+
+    #[test]
+    fn test_drop_oldest() {
+        let buffer = OfflineBuffer::new(2);
+        buffer.push("a", json!(1));
+        buffer.push("b", json!(2));
+        buffer.push("c", json!(3));
+        let topics = buffer
+            .take()
+            .into_iter()
+            .map(|reading| reading.topic)
+            .collect::<Vec<_>>();
+        assert_eq!(topics, ["b", "c"]);
+        assert!(buffer.take().is_empty());
+    }
+
+    #[test]
+    fn test_disabled() {
+        let buffer = OfflineBuffer::new(0);
+        buffer.push("a", json!(1));
+        assert!(buffer.take().is_empty());
+    }
+}
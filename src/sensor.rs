@@ -2,15 +2,24 @@ use crate::ha::values::{DeviceClass, EntityCategory, StateClass};
 use anyhow::Error;
 use serde::Serialize;
 use std::borrow::Cow;
+use tokio::sync::Notify;
 
 pub trait Sensor: 'static {
     type Payload: Serialize + 'static;
 
+    /// Whether the status should be published as a retained message.
+    const RETAIN: bool = false;
+
     fn topic(&self) -> &str;
 
     fn discovery_data(&self) -> Vec<SensorDiscovery<'_>>;
 
     async fn get_status(&self) -> Result<Self::Payload, Error>;
+
+    /// Notified when the status should be published right away, outside the regular interval.
+    fn trigger(&self) -> Option<&Notify> {
+        None
+    }
 }
 
 pub struct SensorDiscovery<'a> {
@@ -23,6 +32,8 @@ pub struct SensorDiscovery<'a> {
     pub suggested_display_precision: Option<i32>,
     pub icon: Option<&'static str>,
     pub binary: bool,
+    /// Whether the entity stays available while the device is offline.
+    pub always_available: bool,
     pub value_template: Cow<'a, str>,
     pub attributes_template: Option<&'static str>,
 }
@@ -39,6 +50,7 @@ impl<'a> SensorDiscovery<'a> {
             suggested_display_precision: None,
             icon: Some(init.icon),
             binary: false,
+            always_available: false,
             value_template: init.value_template.into(),
             attributes_template: None,
         }
@@ -74,6 +86,11 @@ impl<'a> SensorDiscovery<'a> {
         self
     }
 
+    pub fn with_always_available(mut self, always_available: bool) -> Self {
+        self.always_available = always_available;
+        self
+    }
+
     pub fn with_attributes(mut self, template: &'static str) -> Self {
         self.attributes_template = Some(template);
         self
@@ -1,13 +1,18 @@
+use crate::metrics::{METRICS, Metrics};
+use crate::offline_buffer::OfflineBuffer;
 use crate::sensor::Sensor;
 use crate::sensors::Sensors;
 use anyhow::{Context, Error};
-use log::{debug, error};
+use futures_util::future::join_all;
+use log::{debug, error, info};
 use rumqttc::{AsyncClient, QoS};
 use std::any::type_name;
 
 pub struct SensorPublisher<'a> {
-    pub client: &'a AsyncClient,
+    /// Client to publish to, or `None` if readings should go to the offline buffer instead.
+    pub client: Option<&'a AsyncClient>,
     pub sensors: &'a Sensors,
+    pub offline_buffer: &'a OfflineBuffer,
 }
 
 impl<'a> SensorPublisher<'a> {
@@ -15,39 +20,200 @@ impl<'a> SensorPublisher<'a> {
         let Sensors {
             monitor_sensor,
             cpu_sensor,
+            cpufreq_sensor,
             memory_sensor,
+            swap_sensor,
             disk_sensor,
             load_sensor,
+            pressure_sensor,
+            rapl_sensor,
             net_sensor,
+            os_sensor,
+            uptime_sensor,
+            sessions_sensor,
+            desktop_sensor,
             apt_sensor,
+            apt_job_sensor,
+            snap_sensor,
+            unattended_upgrades_sensor,
             reboot_sensor,
+            restart_sensor,
+            resume_sensor,
+            power_state_sensor,
+            custom_command_sensors,
+            custom_file_sensors,
+            custom_command_result_sensors,
+            switch_sensors,
         } = self.sensors;
 
         tokio::join!(
             self.publish_payload(monitor_sensor),
             self.publish_payload(cpu_sensor),
+            self.publish_payloads(cpufreq_sensor.as_slice()),
             self.publish_payload(memory_sensor),
+            self.publish_payload(swap_sensor),
             self.publish_payload(disk_sensor),
             self.publish_payload(load_sensor),
+            self.publish_payloads(pressure_sensor.as_slice()),
+            self.publish_payloads(rapl_sensor.as_slice()),
             self.publish_payload(net_sensor),
+            self.publish_payload(os_sensor),
+            self.publish_payload(uptime_sensor),
+            self.publish_payload(sessions_sensor),
+            self.publish_payloads(desktop_sensor.as_slice()),
             self.publish_payload(apt_sensor),
+            self.publish_payload(apt_job_sensor),
+            self.publish_payloads(snap_sensor.as_slice()),
+            self.publish_payload(unattended_upgrades_sensor),
             self.publish_payload(reboot_sensor),
+            self.publish_payload(restart_sensor),
+            self.publish_payload(resume_sensor),
+            self.publish_payload(power_state_sensor),
+            self.publish_payloads(custom_command_sensors),
+            self.publish_payloads(custom_file_sensors),
+            self.publish_payloads(custom_command_result_sensors),
+            self.publish_payloads(switch_sensors),
         );
     }
 
-    async fn publish_payload<S: Sensor>(&self, sensor: &S) {
+    /// Publish status of sensors whenever they request so via their triggers.
+    pub async fn publish_on_trigger(&self) {
+        let Sensors {
+            monitor_sensor,
+            cpu_sensor,
+            cpufreq_sensor,
+            memory_sensor,
+            swap_sensor,
+            disk_sensor,
+            load_sensor,
+            pressure_sensor,
+            rapl_sensor,
+            net_sensor,
+            os_sensor,
+            uptime_sensor,
+            sessions_sensor,
+            desktop_sensor,
+            apt_sensor,
+            apt_job_sensor,
+            snap_sensor,
+            unattended_upgrades_sensor,
+            reboot_sensor,
+            restart_sensor,
+            resume_sensor,
+            power_state_sensor,
+            custom_command_sensors,
+            custom_file_sensors,
+            custom_command_result_sensors,
+            switch_sensors,
+        } = self.sensors;
+
+        tokio::join!(
+            self.publish_payload_on_trigger(monitor_sensor),
+            self.publish_payload_on_trigger(cpu_sensor),
+            self.publish_payloads_on_trigger(cpufreq_sensor.as_slice()),
+            self.publish_payload_on_trigger(memory_sensor),
+            self.publish_payload_on_trigger(swap_sensor),
+            self.publish_payload_on_trigger(disk_sensor),
+            self.publish_payload_on_trigger(load_sensor),
+            self.publish_payloads_on_trigger(pressure_sensor.as_slice()),
+            self.publish_payloads_on_trigger(rapl_sensor.as_slice()),
+            self.publish_payload_on_trigger(net_sensor),
+            self.publish_payload_on_trigger(os_sensor),
+            self.publish_payload_on_trigger(uptime_sensor),
+            self.publish_payload_on_trigger(sessions_sensor),
+            self.publish_payloads_on_trigger(desktop_sensor.as_slice()),
+            self.publish_payload_on_trigger(apt_sensor),
+            self.publish_payload_on_trigger(apt_job_sensor),
+            self.publish_payloads_on_trigger(snap_sensor.as_slice()),
+            self.publish_payload_on_trigger(unattended_upgrades_sensor),
+            self.publish_payload_on_trigger(reboot_sensor),
+            self.publish_payload_on_trigger(restart_sensor),
+            self.publish_payload_on_trigger(resume_sensor),
+            self.publish_payload_on_trigger(power_state_sensor),
+            self.publish_payloads_on_trigger(custom_command_sensors),
+            self.publish_payloads_on_trigger(custom_file_sensors),
+            self.publish_payloads_on_trigger(custom_command_result_sensors),
+            self.publish_payloads_on_trigger(switch_sensors),
+        );
+    }
+
+    /// Publish readings buffered while the broker was unreachable.
+    ///
+    /// Each reading is published with its timestamp to `{sensor_topic}/buffered`,
+    /// so that it doesn't get mistaken for the current state.
+    pub async fn replay_buffered(&self) {
+        let Some(client) = self.client else {
+            return;
+        };
+        let readings = self.offline_buffer.take();
+        if readings.is_empty() {
+            return;
+        }
+        info!("Replaying {} buffered readings...", readings.len());
+        for reading in readings {
+            let topic = format!("{}/buffered", reading.topic);
+            let payload = match serde_json::to_string(&reading.message) {
+                Ok(payload) => payload,
+                Err(e) => {
+                    error!("Failed to serialize buffered reading for {topic}: {e}");
+                    continue;
+                }
+            };
+            debug!("Publishing {} to {}", payload, topic);
+            if let Err(e) = client
+                .publish(&topic, QoS::AtLeastOnce, false, payload)
+                .await
+            {
+                error!("Failed to replay buffered readings: {e}");
+                break;
+            }
+        }
+    }
+
+    async fn publish_payloads<S: Sensor>(&self, sensors: &[S]) {
+        join_all(sensors.iter().map(|sensor| self.publish_payload(sensor))).await;
+    }
+
+    async fn publish_payloads_on_trigger<S: Sensor>(&self, sensors: &[S]) {
+        join_all(
+            sensors
+                .iter()
+                .map(|sensor| self.publish_payload_on_trigger(sensor)),
+        )
+        .await;
+    }
+
+    async fn publish_payload_on_trigger<S: Sensor>(&self, sensor: &S) {
+        let Some(trigger) = sensor.trigger() else {
+            return;
+        };
+        loop {
+            trigger.notified().await;
+            self.publish_payload(sensor).await;
+        }
+    }
+
+    pub async fn publish_payload<S: Sensor>(&self, sensor: &S) {
         if let Err(e) = self.publish_payload_inner(sensor).await {
             let name = type_name::<S>();
             error!("Failed to publish for {name}: {e}");
+            Metrics::increment(&METRICS.publish_failures);
         }
     }
 
     async fn publish_payload_inner<S: Sensor>(&self, sensor: &S) -> Result<(), Error> {
         let status = sensor.get_status().await.context("Failed to read status")?;
-        let payload = serde_json::to_string(&status).context("Failed to serialize payload")?;
+        let payload = serde_json::to_value(&status).context("Failed to serialize payload")?;
+        METRICS.record_reading(sensor.topic(), payload.clone());
+        let Some(client) = self.client else {
+            debug!("Buffering {} for {}", payload, sensor.topic());
+            self.offline_buffer.push(sensor.topic(), payload);
+            return Ok(());
+        };
+        let payload = payload.to_string();
         debug!("Publishing {} to {}", payload, sensor.topic());
-        self.client
-            .publish(sensor.topic(), QoS::AtLeastOnce, false, payload)
+        client
+            .publish(sensor.topic(), QoS::AtLeastOnce, S::RETAIN, payload)
             .await
             .context("Failed to publish status")?;
         Ok(())
@@ -1,23 +1,45 @@
-use crate::ha::values::StateClass;
+use crate::commands::AptJobs;
+use crate::ha::values::{EntityCategory, StateClass};
 use crate::sensor::{Sensor, SensorDiscovery, SensorDiscoveryInit};
 use anyhow::{Context, Error, ensure};
 use regex::Regex;
 use serde::Serialize;
-use std::process::Stdio;
-use std::sync::LazyLock;
-use tokio::io::{AsyncBufReadExt, BufReader};
+use std::fmt::Write as _;
+use std::sync::{Arc, LazyLock, Mutex};
 use tokio::process::Command;
+use tokio::sync::Notify;
 
-const ID: &str = "apt";
+pub(crate) const ID: &str = "apt";
+
+/// Maximum length of release summary accepted by Home Assistant.
+const MAX_RELEASE_SUMMARY_LEN: usize = 255;
 
 pub struct AptSensor {
     topic: Box<str>,
+    apt_jobs: Arc<AptJobs>,
+    /// Upgrades found last time, reported again while an upgrade is in progress.
+    last_upgrades: Mutex<Upgrades>,
 }
 
 impl AptSensor {
-    pub fn new(topic_base: &str) -> Self {
+    pub fn new(topic_base: &str, apt_jobs: Arc<AptJobs>) -> Self {
         let topic = format!("{topic_base}/{ID}").into_boxed_str();
-        AptSensor { topic }
+        AptSensor {
+            topic,
+            apt_jobs,
+            last_upgrades: Mutex::default(),
+        }
+    }
+
+    async fn list_upgrades(&self) -> Result<Upgrades, Error> {
+        let output = Command::new("apt-get")
+            .args(["--just-print", "upgrade"])
+            .env("LANG", "C")
+            .output()
+            .await
+            .context("Failed to invoke apt-get")?;
+        ensure!(output.status.success(), "apt-get command failed");
+        Ok(parse_output(&String::from_utf8_lossy(&output.stdout)))
     }
 }
 
@@ -38,43 +60,71 @@ impl Sensor for AptSensor {
             })
             .with_state_class(StateClass::Measurement)
             .with_attributes("{{ value_json.attrs | tojson }}"),
+            SensorDiscovery::new(SensorDiscoveryInit {
+                id: "apt_security",
+                title: "APT pending security upgrades",
+                icon: "mdi:security",
+                value_template: "{{ value_json.security.state }}",
+            })
+            .with_state_class(StateClass::Measurement)
+            .with_attributes("{{ value_json.security.attrs | tojson }}"),
+            SensorDiscovery::new(SensorDiscoveryInit {
+                id: "apt_phased",
+                title: "APT phased upgrades deferred",
+                icon: "mdi:timer-sand",
+                value_template: "{{ value_json.phased.state }}",
+            })
+            .with_entity_category(EntityCategory::Diagnostic)
+            .with_state_class(StateClass::Measurement)
+            .with_attributes("{{ value_json.phased.attrs | tojson }}"),
         ]
     }
 
     async fn get_status(&self) -> Result<Self::Payload, Error> {
-        let mut child = Command::new("apt-get")
-            .args(["--just-print", "upgrade"])
-            .env("LANG", "C")
-            .stdout(Stdio::piped())
-            .spawn()
-            .context("Failed to invoke apt-get")?;
-
-        let stdout = child.stdout.take().context("Failed to take stdout")?;
-        let handle = tokio::spawn(async move {
-            let mut list = Vec::new();
-            let mut reader = BufReader::new(stdout).lines();
-            while let Some(line) = reader.next_line().await? {
-                if let Some(captures) = REGEX_INST.captures(&line) {
-                    list.push(captures.get(1).unwrap().as_str().to_owned());
-                }
+        let job = self.apt_jobs.status();
+        let in_progress = job.is_running();
+        // Avoid listing upgrades while APT is busy.
+        let Upgrades { upgrades, phased } = match in_progress {
+            true => self.last_upgrades.lock().unwrap().clone(),
+            false => {
+                let upgrades = self.list_upgrades().await?;
+                *self.last_upgrades.lock().unwrap() = upgrades.clone();
+                upgrades
             }
-            Ok::<_, Error>(list)
-        });
+        };
 
-        let status = child
-            .wait()
-            .await
-            .context("Failed to wait for apt-get command")?;
-        ensure!(status.success(), "apt-get command failed");
-
-        let list = handle
-            .await
-            .context("Failed to wait for apt-get output parsing")?
-            .context("Failed to parse apt-get output")?;
+        let update = Update {
+            installed_version: "installed",
+            latest_version: match upgrades.len() {
+                0 => "installed".to_owned(),
+                1 => "1 package pending".to_owned(),
+                n => format!("{n} packages pending"),
+            },
+            title: "APT packages",
+            release_summary: make_release_summary(&upgrades),
+            in_progress,
+            update_percentage: job.percentage.filter(|_| in_progress),
+        };
+        let security = upgrades
+            .iter()
+            .filter(|u| u.security)
+            .map(|u| u.package.clone())
+            .collect();
+        let attrs = Attrs {
+            packages: upgrades.iter().map(|u| u.package.clone()).collect(),
+            upgrades,
+        };
+        Ok(Payload {
+            state: attrs.packages.len(),
+            attrs,
+            security: PackageCount::new(security),
+            phased: PackageCount::new(phased),
+            update,
+        })
+    }
 
-        let state = list.len();
-        let attrs = Attrs { packages: list };
-        Ok(Payload { state, attrs })
+    fn trigger(&self) -> Option<&Notify> {
+        Some(&self.apt_jobs.progress_changed)
     }
 }
 
@@ -82,11 +132,170 @@ impl Sensor for AptSensor {
 pub struct Payload {
     state: usize,
     attrs: Attrs,
+    security: PackageCount,
+    /// Upgrades deferred by phased updates of Ubuntu.
+    phased: PackageCount,
+    /// State of the update entity.
+    update: Update,
 }
 
 #[derive(Serialize)]
 struct Attrs {
     packages: Vec<String>,
+    upgrades: Vec<Upgrade>,
 }
 
-static REGEX_INST: LazyLock<Regex> = LazyLock::new(|| Regex::new("^Inst ([^ ]+)").unwrap());
+#[derive(Serialize)]
+struct PackageCount {
+    state: usize,
+    attrs: PackageCountAttrs,
+}
+
+#[derive(Serialize)]
+struct PackageCountAttrs {
+    packages: Vec<String>,
+}
+
+impl PackageCount {
+    fn new(packages: Vec<String>) -> Self {
+        PackageCount {
+            state: packages.len(),
+            attrs: PackageCountAttrs { packages },
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct Upgrades {
+    upgrades: Vec<Upgrade>,
+    /// Packages with upgrades deferred due to phasing.
+    phased: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+struct Upgrade {
+    package: String,
+    /// Installed version, or `None` for newly installed packages.
+    installed: Option<String>,
+    candidate: String,
+    /// Archives the candidate comes from, e.g. `noble-updates`.
+    archives: Vec<String>,
+    /// Whether the candidate comes from a security archive.
+    security: bool,
+}
+
+#[derive(Serialize)]
+struct Update {
+    installed_version: &'static str,
+    latest_version: String,
+    title: &'static str,
+    release_summary: String,
+    in_progress: bool,
+    update_percentage: Option<f32>,
+}
+
+static REGEX_INST: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^Inst (?<package>[^ ]+) (?:\[(?<installed>[^\]]+)\] )?\((?<candidate>[^ ]+) (?<archives>[^\[)]*)",
+    )
+    .unwrap()
+});
+
+const PHASING_HEADER: &str = "The following upgrades have been deferred due to phasing:";
+
+/// Parse the output of `apt-get --just-print upgrade`.
+fn parse_output(output: &str) -> Upgrades {
+    let mut result = Upgrades::default();
+    let mut in_phasing = false;
+    for line in output.lines() {
+        if line == PHASING_HEADER {
+            in_phasing = true;
+            continue;
+        }
+        // Package lists are indented under their headers.
+        if in_phasing && line.starts_with(' ') {
+            result
+                .phased
+                .extend(line.split_whitespace().map(str::to_owned));
+            continue;
+        }
+        in_phasing = false;
+        result.upgrades.extend(parse_inst_line(line));
+    }
+    result
+}
+
+/// Parse an `Inst` line from `apt-get --just-print`,
+/// e.g. `Inst libc6 [2.39-0ubuntu8.3] (2.39-0ubuntu8.4 Ubuntu:24.04/noble-updates [amd64])`.
+fn parse_inst_line(line: &str) -> Option<Upgrade> {
+    let captures = REGEX_INST.captures(line)?;
+    // Archives are listed like `Ubuntu:24.04/noble-updates, Ubuntu:24.04/noble-security`.
+    let archives = captures["archives"]
+        .split(", ")
+        .filter_map(|archive| archive.trim().rsplit_once('/'))
+        .map(|(_, suite)| suite.to_owned())
+        .collect::<Vec<_>>();
+    let security = archives.iter().any(|suite| suite.ends_with("-security"));
+    Some(Upgrade {
+        package: captures["package"].to_owned(),
+        installed: captures.name("installed").map(|m| m.as_str().to_owned()),
+        candidate: captures["candidate"].to_owned(),
+        archives,
+        security,
+    })
+}
+
+/// List upgrades for the update entity, as many as fit in its release summary.
+fn make_release_summary(upgrades: &[Upgrade]) -> String {
+    let mut summary = String::new();
+    for (i, upgrade) in upgrades.iter().enumerate() {
+        let mut line = format!("- {}: ", upgrade.package);
+        if let Some(installed) = &upgrade.installed {
+            write!(line, "{installed} → ").unwrap();
+        }
+        writeln!(line, "{}", upgrade.candidate).unwrap();
+        // Leave room for the line of remaining upgrades.
+        if summary.len() + line.len() > MAX_RELEASE_SUMMARY_LEN - 20 {
+            write!(summary, "…and {} more", upgrades.len() - i).unwrap();
+            break;
+        }
+        summary.push_str(&line);
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Upgrade, make_release_summary, parse_output};
+
+    #[test]
+    fn test_parse_output() {
+        let output = include_str!("assets/apt_upgrade_1");
+        let result = parse_output(output);
+        let packages = result
+            .upgrades
+            .iter()
+            .map(|u| u.package.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(packages, ["libc6", "libc-bin", "openssl", "tzdata"]);
+        assert_eq!(
+            result.upgrades[2],
+            Upgrade {
+                package: "openssl".to_owned(),
+                installed: Some("3.0.13-0ubuntu3.4".to_owned()),
+                candidate: "3.0.13-0ubuntu3.5".to_owned(),
+                archives: vec!["noble-updates".to_owned(), "noble-security".to_owned()],
+                security: true,
+            }
+        );
+        assert!(!result.upgrades[3].security);
+        assert_eq!(
+            result.phased,
+            ["python3-distupgrade", "ubuntu-release-upgrader-core"]
+        );
+        assert_eq!(
+            make_release_summary(&result.upgrades[3..]),
+            "- tzdata: 2024a-3ubuntu1.1 → 2024b-0ubuntu0.24.04\n"
+        );
+    }
+}
@@ -0,0 +1,71 @@
+use crate::commands::{AptJobs, JobState, JobStatus};
+use crate::ha::values::{EntityCategory, StateClass};
+use crate::sensor::{Sensor, SensorDiscovery, SensorDiscoveryInit};
+use anyhow::Error;
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::Notify;
+
+const ID: &str = "apt_job";
+
+/// Reports the current or last job run by APT commands.
+pub struct AptJobSensor {
+    topic: Box<str>,
+    apt_jobs: Arc<AptJobs>,
+}
+
+impl AptJobSensor {
+    pub fn new(topic_base: &str, apt_jobs: Arc<AptJobs>) -> Self {
+        let topic = format!("{topic_base}/{ID}").into_boxed_str();
+        AptJobSensor { topic, apt_jobs }
+    }
+}
+
+impl Sensor for AptJobSensor {
+    type Payload = Payload;
+
+    fn topic(&self) -> &str {
+        self.topic.as_ref()
+    }
+
+    fn discovery_data(&self) -> Vec<SensorDiscovery<'_>> {
+        vec![
+            SensorDiscovery::new(SensorDiscoveryInit {
+                id: ID,
+                title: "APT job",
+                icon: "mdi:package-variant",
+                value_template: "{{ value_json.state }}",
+            })
+            .with_entity_category(EntityCategory::Diagnostic)
+            .with_attributes("{{ value_json.attrs | tojson }}"),
+            SensorDiscovery::new(SensorDiscoveryInit {
+                id: "apt_job_progress",
+                title: "APT job progress",
+                icon: "mdi:progress-download",
+                value_template: "{{ value_json.attrs.percentage }}",
+            })
+            .with_entity_category(EntityCategory::Diagnostic)
+            .with_state_class(StateClass::Measurement)
+            .with_unit_of_measurement("%")
+            .with_suggested_display_precision(0),
+        ]
+    }
+
+    async fn get_status(&self) -> Result<Self::Payload, Error> {
+        let attrs = self.apt_jobs.status();
+        Ok(Payload {
+            state: attrs.state,
+            attrs,
+        })
+    }
+
+    fn trigger(&self) -> Option<&Notify> {
+        Some(&self.apt_jobs.status_changed)
+    }
+}
+
+#[derive(Serialize)]
+pub struct Payload {
+    state: JobState,
+    attrs: JobStatus,
+}
@@ -1,3 +1,5 @@
+use super::sampler::{Sample, Sampler};
+use crate::config::Cpu as CpuConfig;
 use crate::ha::values::{EntityCategory, StateClass};
 use crate::sensor::{Sensor, SensorDiscovery, SensorDiscoveryInit};
 use crate::utils::parser::{parse_next_field, parse_next_field_opt};
@@ -6,67 +8,98 @@ use nix::unistd::{SysconfVar, sysconf};
 use serde::Serialize;
 use std::fs;
 use std::str::FromStr;
-use std::time::{Duration, Instant};
-use tokio::sync::{Mutex, watch};
+use std::time::Duration;
 
 const ID: &str = "cpu";
 
 pub struct CpuSensor {
     topic: Box<str>,
     cpu_count: usize,
-    rx: Mutex<watch::Receiver<Payload>>,
+    per_cpu_breakdown: bool,
+    sampler: Sampler<Payload>,
 }
 
 impl CpuSensor {
-    pub fn new(topic_base: &str) -> Result<Self, Error> {
+    pub fn new(topic_base: &str, config: &CpuConfig) -> Result<Self, Error> {
         let topic = format!("{topic_base}/{ID}").into_boxed_str();
-        let mut last_obs = CpuTimesObservation::new()?;
-        let cpu_count = last_obs.observation.per_cpu.len();
         let clock_tick = sysconf(SysconfVar::CLK_TCK)
             .context("Failed to read clock tick")?
             .context("Expected clock tick to be available")? as u64;
-        let (tx, rx) = watch::channel(Default::default());
-        tokio::spawn(async move {
-            loop {
-                // Calculate average CPU use over 1 minute.
-                tokio::time::sleep(Duration::from_secs(60)).await;
-                let obs = CpuTimesObservation::new()?;
-                let duration = obs.timestamp - last_obs.timestamp;
-                let total = round_percentage(
-                    calculate_percentage(
-                        &last_obs.observation.total,
-                        &obs.observation.total,
-                        clock_tick,
-                        duration,
-                    ) / cpu_count as f32,
-                );
-                let per_cpu = if cpu_count > 1 {
-                    Iterator::zip(
-                        last_obs.observation.per_cpu.iter(),
-                        obs.observation.per_cpu.iter(),
-                    )
-                    .map(|(start, end)| {
-                        round_percentage(calculate_percentage(start, end, clock_tick, duration))
-                    })
-                    .collect()
-                } else {
-                    Vec::new()
-                };
-                tx.send(Payload { total, per_cpu })
-                    .context("Failed to update payload")?;
-                last_obs = obs;
-            }
-
-            // This is to specify the return type so that `?` may be used above.
-            #[allow(unreachable_code)]
-            Ok::<(), Error>(())
-        });
+        let first = read_stat()?;
+        let cpu_count = first.per_cpu.len();
+        let per_cpu_breakdown = config.per_cpu_breakdown && cpu_count > 1;
+        let sample = CpuSample {
+            clock_tick,
+            cpu_count,
+            per_cpu_breakdown,
+        };
         Ok(CpuSensor {
             topic,
             cpu_count,
-            rx: Mutex::new(rx),
+            per_cpu_breakdown,
+            sampler: Sampler::start(sample, first),
         })
     }
+
+    pub fn resample(&self) {
+        self.sampler.resample();
+    }
+}
+
+/// Average CPU use over the sample.
+struct CpuSample {
+    clock_tick: u64,
+    cpu_count: usize,
+    per_cpu_breakdown: bool,
+}
+
+impl Sample for CpuSample {
+    type Observation = AllCpuTimes;
+    type Payload = Payload;
+
+    fn observe(&self) -> Result<AllCpuTimes, Error> {
+        read_stat()
+    }
+
+    fn calculate(&mut self, start: &AllCpuTimes, end: &AllCpuTimes, duration: Duration) -> Payload {
+        let percentage = |start, end, field, scale| {
+            round_percentage(
+                calculate_percentage(start, end, field, self.clock_tick, duration) / scale,
+            )
+        };
+        let breakdown = |start, end, scale| Breakdown {
+            user: percentage(start, end, CpuTimes::user_time, scale),
+            system: percentage(start, end, |t| t.system, scale),
+            iowait: percentage(start, end, |t| t.io_wait, scale),
+            irq: percentage(start, end, CpuTimes::irq_time, scale),
+            steal: percentage(start, end, |t| t.steal, scale),
+        };
+        // Total times add up all CPUs, so scale them down to be out of 100%.
+        let cpu_count = self.cpu_count as f32;
+        let total = percentage(&start.total, &end.total, CpuTimes::busy, cpu_count);
+        let total_breakdown = breakdown(&start.total, &end.total, cpu_count);
+        let per_cpu_pairs = || Iterator::zip(start.per_cpu.iter(), end.per_cpu.iter());
+        let per_cpu = if self.cpu_count > 1 {
+            per_cpu_pairs()
+                .map(|(start, end)| percentage(start, end, CpuTimes::busy, 1.))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let per_cpu_breakdown = if self.per_cpu_breakdown {
+            per_cpu_pairs()
+                .map(|(start, end)| breakdown(start, end, 1.))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        Payload {
+            total,
+            per_cpu,
+            breakdown: total_breakdown,
+            per_cpu_breakdown,
+        }
+    }
 }
 
 impl Sensor for CpuSensor {
@@ -95,6 +128,14 @@ impl Sensor for CpuSensor {
             entity_category: None,
             ..base_discovery
         });
+        for (field, title) in BREAKDOWN_FIELDS {
+            data.push(SensorDiscovery {
+                id: format!("cpu_{field}").into(),
+                title: format!("CPU {title}").into(),
+                value_template: format!("{{{{ value_json.breakdown.{field} }}}}").into(),
+                ..base_discovery
+            });
+        }
         if self.cpu_count > 1 {
             for i in 0..self.cpu_count {
                 data.push(SensorDiscovery {
@@ -105,14 +146,26 @@ impl Sensor for CpuSensor {
                 });
             }
         }
+        if self.per_cpu_breakdown {
+            for i in 0..self.cpu_count {
+                for (field, title) in BREAKDOWN_FIELDS {
+                    data.push(SensorDiscovery {
+                        id: format!("cpu_{i}_{field}").into(),
+                        title: format!("CPU {i} {title}").into(),
+                        value_template: format!(
+                            "{{{{ value_json.per_cpu_breakdown[{i}].{field} }}}}"
+                        )
+                        .into(),
+                        ..base_discovery
+                    });
+                }
+            }
+        }
         data
     }
 
     async fn get_status(&self) -> Result<Self::Payload, Error> {
-        let mut rx = self.rx.try_lock().context("Failed to acquire receiver")?;
-        rx.changed().await.context("Failed to wait for receiver")?;
-        let payload = rx.borrow_and_update().clone();
-        Ok(payload)
+        self.sampler.next().await
     }
 }
 
@@ -121,23 +174,35 @@ pub struct Payload {
     total: f32,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     per_cpu: Vec<f32>,
+    breakdown: Breakdown,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    per_cpu_breakdown: Vec<Breakdown>,
 }
 
-struct CpuTimesObservation {
-    timestamp: Instant,
-    observation: AllCpuTimes,
+/// Fields of [`Breakdown`] along with their titles.
+const BREAKDOWN_FIELDS: [(&str, &str); 5] = [
+    ("user", "user"),
+    ("system", "system"),
+    ("iowait", "IO wait"),
+    ("irq", "IRQ"),
+    ("steal", "steal"),
+];
+
+/// Percentages of CPU time spent on different things.
+#[derive(Clone, Default, Serialize)]
+struct Breakdown {
+    user: f32,
+    system: f32,
+    iowait: f32,
+    /// Time servicing hardware and software interrupts.
+    irq: f32,
+    /// Time taken by the hypervisor for other virtual machines.
+    steal: f32,
 }
 
-impl CpuTimesObservation {
-    fn new() -> Result<Self, Error> {
-        let timestamp = Instant::now();
-        let stat = fs::read_to_string("/proc/stat").context("Failed to read stat file")?;
-        let observation = parse_stat(&stat).context("Failed to parse stat file")?;
-        Ok(Self {
-            timestamp,
-            observation,
-        })
-    }
+fn read_stat() -> Result<AllCpuTimes, Error> {
+    let stat = fs::read_to_string("/proc/stat").context("Failed to read stat file")?;
+    parse_stat(&stat).context("Failed to parse stat file")
 }
 
 fn parse_stat(s: &str) -> Result<AllCpuTimes, Error> {
@@ -187,9 +252,20 @@ struct CpuTimes {
 }
 
 impl CpuTimes {
+    /// Time the CPU is busy for this system.
+    ///
+    /// Guest time is not added, as it's already included in user time.
+    /// Neither is steal time, as it's not spent for this system.
     fn busy(&self) -> u64 {
-        // XXX: Is this the right way to count busy time?
-        self.user + self.nice + self.system + self.guest + self.guest_nice
+        self.user_time() + self.system + self.irq_time()
+    }
+
+    fn user_time(&self) -> u64 {
+        self.user + self.nice
+    }
+
+    fn irq_time(&self) -> u64 {
+        self.irq + self.soft_irq
     }
 }
 
@@ -226,12 +302,14 @@ impl FromStr for CpuTimes {
 fn calculate_percentage(
     start: &CpuTimes,
     end: &CpuTimes,
+    field: fn(&CpuTimes) -> u64,
     clock_tick: u64,
     duration: Duration,
 ) -> f32 {
-    let start = start.busy();
-    let end = end.busy();
-    let delta = (end - start) as f32 / clock_tick as f32;
+    let start = field(start);
+    let end = field(end);
+    // Some times, e.g. IO wait, may go backwards, see proc(5).
+    let delta = end.saturating_sub(start) as f32 / clock_tick as f32;
     delta / duration.as_secs_f32() * 100.
 }
 
@@ -241,7 +319,22 @@ fn round_percentage(p: f32) -> f32 {
 
 #[cfg(test)]
 mod tests {
-    use super::{AllCpuTimes, CpuTimes, parse_stat};
+    use super::{AllCpuTimes, CpuTimes, calculate_percentage, parse_stat};
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    #[test]
+    fn test_busy() {
+        let start = CpuTimes::from_str("100 10 50 1000 20 5 15 30 40 0").unwrap();
+        let end = CpuTimes::from_str("200 20 100 1800 40 10 30 60 80 0").unwrap();
+        let duration = Duration::from_secs(10);
+        // Guest time is part of user time, and steal time isn't busy.
+        assert_eq!(start.busy(), 100 + 10 + 50 + 5 + 15);
+        let busy = calculate_percentage(&start, &end, CpuTimes::busy, 100, duration);
+        assert_eq!(busy, 18.);
+        let steal = calculate_percentage(&start, &end, |t| t.steal, 100, duration);
+        assert_eq!(steal, 3.);
+    }
 
     #[test]
     fn test_stat() {
@@ -0,0 +1,244 @@
+use crate::ha::values::{DeviceClass, EntityCategory, StateClass};
+use crate::sensor::{Sensor, SensorDiscovery, SensorDiscoveryInit};
+use anyhow::{Context, Error};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Notify;
+
+pub(crate) const ID: &str = "cpufreq";
+
+const CPU_DIR: &str = "/sys/devices/system/cpu";
+
+/// Frequency, governor and thermal throttling of CPUs.
+pub struct CpuFreqSensor {
+    topic: Box<str>,
+    /// Whether CPUs report thermal throttling, which only Intel ones do.
+    has_throttle: bool,
+    /// Notified when the governor is set.
+    governor_changed: Option<Arc<Notify>>,
+}
+
+impl CpuFreqSensor {
+    /// Create the sensor, or `None` if CPU frequency scaling is not available.
+    pub fn new(topic_base: &str, governor_changed: Option<Arc<Notify>>) -> Option<Self> {
+        let cpu0 = Path::new(CPU_DIR).join("cpu0");
+        if !cpu0.join("cpufreq").exists() {
+            return None;
+        }
+        let topic = format!("{topic_base}/{ID}").into_boxed_str();
+        let has_throttle = cpu0.join("thermal_throttle").exists();
+        Some(CpuFreqSensor {
+            topic,
+            has_throttle,
+            governor_changed,
+        })
+    }
+}
+
+impl Sensor for CpuFreqSensor {
+    type Payload = Payload;
+
+    fn topic(&self) -> &str {
+        self.topic.as_ref()
+    }
+
+    fn discovery_data(&self) -> Vec<SensorDiscovery<'_>> {
+        let base_discovery = SensorDiscovery::new(SensorDiscoveryInit {
+            id: "",
+            title: "",
+            icon: "mdi:sine-wave",
+            value_template: "",
+        })
+        .with_entity_category(EntityCategory::Diagnostic)
+        .with_device_class(DeviceClass::Frequency)
+        .with_state_class(StateClass::Measurement)
+        .with_unit_of_measurement("MHz")
+        .with_suggested_display_precision(0);
+        let mut data = vec![
+            SensorDiscovery {
+                id: "cpu_frequency".into(),
+                title: "CPU frequency".into(),
+                value_template: "{{ value_json.frequency.avg }}".into(),
+                entity_category: None,
+                ..base_discovery
+            },
+            SensorDiscovery {
+                id: "cpu_frequency_min".into(),
+                title: "CPU frequency min".into(),
+                value_template: "{{ value_json.frequency.min }}".into(),
+                ..base_discovery
+            },
+            SensorDiscovery {
+                id: "cpu_frequency_max".into(),
+                title: "CPU frequency max".into(),
+                value_template: "{{ value_json.frequency.max }}".into(),
+                ..base_discovery
+            },
+            SensorDiscovery::new(SensorDiscoveryInit {
+                id: "cpu_governor",
+                title: "CPU governor",
+                icon: "mdi:speedometer",
+                value_template: "{{ value_json.governor }}",
+            })
+            .with_entity_category(EntityCategory::Diagnostic),
+        ];
+        if self.has_throttle {
+            data.push(
+                SensorDiscovery::new(SensorDiscoveryInit {
+                    id: "cpu_throttle",
+                    title: "CPU thermal throttle events",
+                    icon: "mdi:thermometer-alert",
+                    value_template: "{{ value_json.throttle.state }}",
+                })
+                .with_state_class(StateClass::TotalIncreasing)
+                .with_attributes("{{ value_json.throttle.attrs | tojson }}"),
+            );
+        }
+        data
+    }
+
+    async fn get_status(&self) -> Result<Self::Payload, Error> {
+        let mut frequencies = Vec::new();
+        let mut governors = BTreeSet::new();
+        let mut core_throttles = 0;
+        // Package throttle counts are reported by every CPU of the package.
+        let mut package_throttles = BTreeMap::new();
+        for cpu in list_cpus()? {
+            let cpufreq = cpu.join("cpufreq");
+            // Offline CPUs have no frequency.
+            if let Ok(frequency) = read_value::<u64>(&cpufreq.join("scaling_cur_freq")) {
+                frequencies.push(frequency);
+            }
+            if let Ok(governor) = fs::read_to_string(cpufreq.join("scaling_governor")) {
+                governors.insert(governor.trim().to_owned());
+            }
+            if self.has_throttle {
+                let throttle = cpu.join("thermal_throttle");
+                // Offline CPUs have neither throttle counts nor topology.
+                if let (Ok(core), Ok(package), Ok(count)) = (
+                    read_value::<u64>(&throttle.join("core_throttle_count")),
+                    read_value::<u32>(&cpu.join("topology/physical_package_id")),
+                    read_value::<u64>(&throttle.join("package_throttle_count")),
+                ) {
+                    core_throttles += core;
+                    package_throttles.insert(package, count);
+                }
+            }
+        }
+        let package_throttles = package_throttles.values().sum::<u64>();
+        let governor = match governors.len() {
+            1 => governors.pop_first().unwrap(),
+            _ => "mixed".to_owned(),
+        };
+        Ok(Payload {
+            frequency: Frequency::summarize(&frequencies).context("No CPU frequency found")?,
+            governor,
+            throttle: self.has_throttle.then(|| Throttle {
+                state: core_throttles + package_throttles,
+                attrs: ThrottleAttrs {
+                    core: core_throttles,
+                    package: package_throttles,
+                },
+            }),
+        })
+    }
+
+    fn trigger(&self) -> Option<&Notify> {
+        self.governor_changed.as_deref()
+    }
+}
+
+#[derive(Serialize)]
+pub struct Payload {
+    frequency: Frequency,
+    /// Governor of all CPUs, or `mixed` if they differ.
+    governor: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    throttle: Option<Throttle>,
+}
+
+/// Frequencies among CPUs in MHz.
+#[derive(Debug, PartialEq, Serialize)]
+struct Frequency {
+    avg: f32,
+    min: f32,
+    max: f32,
+}
+
+impl Frequency {
+    /// Summarize frequencies given in kHz.
+    fn summarize(frequencies: &[u64]) -> Option<Self> {
+        let min = *frequencies.iter().min()?;
+        let max = *frequencies.iter().max()?;
+        let avg = frequencies.iter().sum::<u64>() / frequencies.len() as u64;
+        let to_mhz = |khz: u64| (khz as f32 / 1000.).round();
+        Some(Frequency {
+            avg: to_mhz(avg),
+            min: to_mhz(min),
+            max: to_mhz(max),
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct Throttle {
+    state: u64,
+    attrs: ThrottleAttrs,
+}
+
+#[derive(Serialize)]
+struct ThrottleAttrs {
+    /// Events of cores throttled.
+    core: u64,
+    /// Events of packages throttled.
+    package: u64,
+}
+
+fn list_cpus() -> Result<Vec<PathBuf>, Error> {
+    let mut cpus = Vec::new();
+    for entry in fs::read_dir(CPU_DIR).context("Failed to list CPUs")? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let is_cpu = name
+            .to_str()
+            .and_then(|name| name.strip_prefix("cpu"))
+            .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()));
+        if is_cpu {
+            cpus.push(entry.path());
+        }
+    }
+    Ok(cpus)
+}
+
+fn read_value<T>(path: &Path) -> Result<T, Error>
+where
+    T: std::str::FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?
+        .trim()
+        .parse()
+        .with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Frequency;
+
+    #[test]
+    fn test_summarize_frequency() {
+        assert_eq!(
+            Frequency::summarize(&[800_012, 3_600_000, 2_400_500, 1_200_000]),
+            Some(Frequency {
+                avg: 2000.,
+                min: 800.,
+                max: 3600.,
+            })
+        );
+        assert_eq!(Frequency::summarize(&[]), None);
+    }
+}
@@ -0,0 +1,157 @@
+use super::{CustomEntity, Payload};
+use crate::config::{self, CommandParser};
+use crate::sensor::{Sensor, SensorDiscovery};
+use anyhow::{Context, Error, ensure};
+use serde_json::Value;
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::sync::Mutex;
+use tokio::time::{Instant, timeout};
+
+pub struct CustomCommandSensor {
+    topic: Box<str>,
+    entity: CustomEntity,
+    command: Vec<String>,
+    timeout: Duration,
+    interval: Option<Duration>,
+    parser: CommandParser,
+    selector: Option<String>,
+    last: Mutex<Option<(Instant, Payload)>>,
+}
+
+impl CustomCommandSensor {
+    pub fn new(topic_base: &str, config: &config::CommandSensor) -> Result<Self, Error> {
+        ensure!(!config.command.is_empty(), "Command is empty");
+        let binary = matches!(config.parser, CommandParser::ExitCode);
+        let entity = CustomEntity::new(&config.id, &config.entity, "mdi:console", binary);
+        let topic = entity.topic(topic_base);
+        let interval = config
+            .interval_in_minutes
+            .map(|minutes| Duration::from_secs(u64::from(minutes) * 60));
+        Ok(CustomCommandSensor {
+            topic,
+            entity,
+            command: config.command.clone(),
+            timeout: Duration::from_secs(config.timeout_in_seconds),
+            interval,
+            parser: config.parser,
+            selector: config.selector.clone(),
+            last: Mutex::new(None),
+        })
+    }
+
+    async fn run_command(&self) -> Result<Value, Error> {
+        let (program, args) = self.command.split_first().unwrap();
+        let output = Command::new(program)
+            .args(args)
+            .env("LANG", "C")
+            .kill_on_drop(true)
+            .output();
+        let output = timeout(self.timeout, output)
+            .await
+            .context("Timeout running command")?
+            .with_context(|| format!("Failed to invoke {program}"))?;
+        if let CommandParser::ExitCode = self.parser {
+            return Ok(Value::Bool(output.status.success()));
+        }
+        ensure!(
+            output.status.success(),
+            "{program} failed with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim(),
+        );
+        let stdout = String::from_utf8(output.stdout).context("Output is not valid UTF-8")?;
+        parse_output(self.parser, self.selector.as_deref(), &stdout)
+    }
+}
+
+impl Sensor for CustomCommandSensor {
+    type Payload = Payload;
+
+    fn topic(&self) -> &str {
+        self.topic.as_ref()
+    }
+
+    fn discovery_data(&self) -> Vec<SensorDiscovery<'_>> {
+        vec![self.entity.discovery_data()]
+    }
+
+    async fn get_status(&self) -> Result<Self::Payload, Error> {
+        let mut last = self.last.lock().await;
+        if let (Some((time, payload)), Some(interval)) = (last.as_ref(), self.interval)
+            && time.elapsed() < interval
+        {
+            return Ok(payload.clone());
+        }
+        let state = self.run_command().await?;
+        let payload = Payload { state };
+        *last = Some((Instant::now(), payload.clone()));
+        Ok(payload)
+    }
+}
+
+fn parse_output(
+    parser: CommandParser,
+    selector: Option<&str>,
+    output: &str,
+) -> Result<Value, Error> {
+    let output = output.trim();
+    Ok(match parser {
+        CommandParser::Number => {
+            let n = output
+                .parse::<f64>()
+                .context("Failed to parse output as number")?;
+            Value::from(n)
+        }
+        CommandParser::String => Value::from(output),
+        CommandParser::Json => {
+            let value = serde_json::from_str(output).context("Failed to parse output as JSON")?;
+            match selector {
+                Some(selector) => select_json(&value, selector)
+                    .with_context(|| format!("Nothing matches {selector}"))?
+                    .clone(),
+                None => value,
+            }
+        }
+        CommandParser::ExitCode => unreachable!("Exit code is not parsed from output"),
+    })
+}
+
+/// Pick a value from JSON with a simple JSONPath-like selector, e.g. `$.foo.bar[0]`.
+fn select_json<'a>(value: &'a Value, selector: &str) -> Option<&'a Value> {
+    let path = selector.strip_prefix('$').unwrap_or(selector);
+    let mut current = value;
+    for segment in path.split('.').filter(|s| !s.is_empty()) {
+        let (key, indices) = segment.split_at(segment.find('[').unwrap_or(segment.len()));
+        if !key.is_empty() {
+            current = current.get(key)?;
+        }
+        for index in indices.split('[').skip(1) {
+            let index = index.strip_suffix(']')?.parse::<usize>().ok()?;
+            current = current.get(index)?;
+        }
+    }
+    Some(current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::select_json;
+    use serde_json::json;
+
+    #[test]
+    fn test_select_json() {
+        let value = json!({ "sensors": [{ "temp": 42 }, { "temp": 43 }], "name": "gpu" });
+        let testcases = [
+            ("$", Some(&value)),
+            ("$.name", Some(&value["name"])),
+            ("$.sensors[1].temp", Some(&value["sensors"][1]["temp"])),
+            ("sensors[0]", Some(&value["sensors"][0])),
+            ("$.sensors[2]", None),
+            ("$.missing", None),
+        ];
+        for (selector, expected) in testcases {
+            assert_eq!(select_json(&value, selector), expected, "{selector}");
+        }
+    }
+}
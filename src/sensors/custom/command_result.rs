@@ -0,0 +1,67 @@
+use crate::commands::{CustomCommand, LastRun, RunResult, RunStatus};
+use crate::ha::values::EntityCategory;
+use crate::sensor::{Sensor, SensorDiscovery, SensorDiscoveryInit};
+use anyhow::Error;
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::Notify;
+
+/// Companion sensor of a custom command, reporting the result of its last run.
+pub struct CustomCommandResultSensor {
+    topic: Box<str>,
+    id: String,
+    title: String,
+    last_run: Arc<LastRun>,
+}
+
+impl CustomCommandResultSensor {
+    pub fn new(topic_base: &str, command: &CustomCommand) -> Self {
+        let id = format!("{}_result", command.id());
+        let topic = format!("{topic_base}/{id}").into_boxed_str();
+        CustomCommandResultSensor {
+            topic,
+            id,
+            title: format!("{} result", command.name()),
+            last_run: command.last_run(),
+        }
+    }
+}
+
+impl Sensor for CustomCommandResultSensor {
+    type Payload = Payload;
+
+    fn topic(&self) -> &str {
+        self.topic.as_ref()
+    }
+
+    fn discovery_data(&self) -> Vec<SensorDiscovery<'_>> {
+        vec![
+            SensorDiscovery::new(SensorDiscoveryInit {
+                id: self.id.as_str(),
+                title: self.title.as_str(),
+                icon: "mdi:console",
+                value_template: "{{ value_json.state }}",
+            })
+            .with_entity_category(EntityCategory::Diagnostic)
+            .with_attributes("{{ value_json.attrs | tojson }}"),
+        ]
+    }
+
+    async fn get_status(&self) -> Result<Self::Payload, Error> {
+        let result = self.last_run.result.lock().unwrap().clone();
+        Ok(Payload {
+            state: result.as_ref().map(|result| result.status),
+            attrs: result,
+        })
+    }
+
+    fn trigger(&self) -> Option<&Notify> {
+        Some(&self.last_run.changed)
+    }
+}
+
+#[derive(Serialize)]
+pub struct Payload {
+    state: Option<RunStatus>,
+    attrs: Option<RunResult>,
+}
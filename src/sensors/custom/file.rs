@@ -0,0 +1,113 @@
+use super::{CustomEntity, Payload};
+use crate::config::{self, FileParser};
+use crate::sensor::{Sensor, SensorDiscovery};
+use crate::utils::parser::parse_next_field;
+use anyhow::{Context, Error};
+use regex::Regex;
+use serde_json::Value;
+use std::path::PathBuf;
+use tokio::fs;
+
+pub struct CustomFileSensor {
+    topic: Box<str>,
+    entity: CustomEntity,
+    path: PathBuf,
+    regex: Option<Regex>,
+    parser: FileParser,
+    scale: f64,
+    offset: f64,
+}
+
+impl CustomFileSensor {
+    pub fn new(topic_base: &str, config: &config::FileSensor) -> Result<Self, Error> {
+        let regex = config
+            .regex
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .context("Invalid regex")?;
+        let binary = matches!(config.parser, FileParser::Binary);
+        let entity = CustomEntity::new(&config.id, &config.entity, "mdi:file-document", binary);
+        let topic = entity.topic(topic_base);
+        Ok(CustomFileSensor {
+            topic,
+            entity,
+            path: config.path.clone(),
+            regex,
+            parser: config.parser,
+            scale: config.scale,
+            offset: config.offset,
+        })
+    }
+
+    fn parse_content(&self, content: &str) -> Result<Value, Error> {
+        let text = match &self.regex {
+            Some(regex) => {
+                let captures = regex.captures(content).context("Regex doesn't match")?;
+                captures
+                    .get(1)
+                    .or_else(|| captures.get(0))
+                    .unwrap()
+                    .as_str()
+            }
+            None => content,
+        };
+        let parse_number = || -> Result<f64, Error> {
+            let n: f64 = parse_next_field(&mut text.split_ascii_whitespace())?;
+            Ok(n * self.scale + self.offset)
+        };
+        Ok(match self.parser {
+            FileParser::Number => Value::from(parse_number()?),
+            FileParser::String => Value::from(text.trim()),
+            FileParser::Binary => Value::from(parse_number()? != 0.),
+        })
+    }
+}
+
+impl Sensor for CustomFileSensor {
+    type Payload = Payload;
+
+    fn topic(&self) -> &str {
+        self.topic.as_ref()
+    }
+
+    fn discovery_data(&self) -> Vec<SensorDiscovery<'_>> {
+        vec![self.entity.discovery_data()]
+    }
+
+    async fn get_status(&self) -> Result<Self::Payload, Error> {
+        let content = fs::read_to_string(&self.path)
+            .await
+            .with_context(|| format!("Failed to read {}", self.path.display()))?;
+        let state = self
+            .parse_content(&content)
+            .with_context(|| format!("Failed to parse {}", self.path.display()))?;
+        Ok(Payload { state })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CustomFileSensor;
+    use crate::config::FileSensor;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_content() {
+        let config: FileSensor = toml::from_str(
+            r#"
+            id = "cpu_temp"
+            name = "CPU temperature"
+            path = "/sys/class/hwmon/hwmon0/temp1_input"
+            regex = 'temp=(\d+)'
+            scale = 0.001
+            offset = 1
+            "#,
+        )
+        .unwrap();
+        let sensor = CustomFileSensor::new("base", &config).unwrap();
+        let value = sensor.parse_content("name=cpu temp=42000\n").unwrap();
+        assert_eq!(value, json!(43.));
+        assert!(sensor.parse_content("temp=\n").is_err());
+    }
+}
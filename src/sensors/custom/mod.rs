@@ -0,0 +1,182 @@
+use crate::config::{Config, Entity};
+use crate::ha::values::{DeviceClass, EntityCategory, StateClass};
+use crate::sensor::{SensorDiscovery, SensorDiscoveryInit};
+use crate::utils::snake_case::make_snake_case;
+use anyhow::{Error, ensure};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashSet;
+
+pub mod command;
+pub mod command_result;
+pub mod file;
+
+/// Check that custom sensors and commands have distinct IDs once made snake case,
+/// as entities with the same ID share a topic and overwrite each other in Home Assistant.
+pub fn check_unique_ids(config: &Config) -> Result<(), Error> {
+    let mut sensor_ids = HashSet::new();
+    let sensors = Iterator::chain(
+        config.command_sensors.iter().map(|sensor| &sensor.id),
+        config.file_sensors.iter().map(|sensor| &sensor.id),
+    );
+    for id in sensors {
+        ensure!(
+            sensor_ids.insert(make_snake_case(id)),
+            "Duplicate ID of custom sensor {id}",
+        );
+    }
+    let mut command_ids = HashSet::new();
+    for command in &config.commands {
+        let id = make_snake_case(&command.id);
+        ensure!(
+            !command_ids.contains(&id),
+            "Duplicate ID of custom command {}",
+            command.id,
+        );
+        // The result of each command is reported by a sensor.
+        ensure!(
+            sensor_ids.insert(format!("{id}_result")),
+            "ID of custom command {} conflicts with a custom sensor",
+            command.id,
+        );
+        command_ids.insert(id);
+    }
+    Ok(())
+}
+
+/// Home Assistant metadata of a custom sensor, prepared from its config.
+struct CustomEntity {
+    id: String,
+    name: String,
+    icon: &'static str,
+    unit_of_measurement: Option<&'static str>,
+    device_class: Option<DeviceClass>,
+    state_class: Option<StateClass>,
+    entity_category: Option<EntityCategory>,
+    binary: bool,
+}
+
+impl CustomEntity {
+    fn new(id: &str, entity: &Entity, default_icon: &'static str, binary: bool) -> Self {
+        // Discovery requires static strings, and custom sensors live as long as the process.
+        let leak = |s: &String| -> &'static str { s.clone().leak() };
+        Self {
+            id: format!("custom_{}", make_snake_case(id)),
+            name: entity.name.clone(),
+            icon: entity.icon.as_ref().map_or(default_icon, leak),
+            unit_of_measurement: entity.unit_of_measurement.as_ref().map(leak),
+            device_class: entity
+                .device_class
+                .as_ref()
+                .map(leak)
+                .map(DeviceClass::Other),
+            state_class: entity.state_class,
+            entity_category: entity.entity_category,
+            binary,
+        }
+    }
+
+    fn topic(&self, topic_base: &str) -> Box<str> {
+        format!("{topic_base}/{}", self.id).into_boxed_str()
+    }
+
+    fn discovery_data(&self) -> SensorDiscovery<'_> {
+        let value_template = match self.binary {
+            true => "{{ 'ON' if value_json.state else 'OFF' }}",
+            false => "{{ value_json.state }}",
+        };
+        let mut discovery = SensorDiscovery::new(SensorDiscoveryInit {
+            id: self.id.as_str(),
+            title: self.name.as_str(),
+            icon: self.icon,
+            value_template,
+        })
+        .with_binary(self.binary);
+        discovery.unit_of_measurement = self.unit_of_measurement;
+        discovery.device_class = self.device_class;
+        discovery.state_class = self.state_class;
+        discovery.entity_category = self.entity_category;
+        discovery
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct Payload {
+    state: Value,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_unique_ids;
+    use crate::config::Config;
+
+    fn parse_config(entities: &str) -> Config {
+        toml::from_str(&format!("[daemon]\n[mqtt]\n{entities}")).unwrap()
+    }
+
+    #[test]
+    fn test_check_unique_ids() {
+        let config = parse_config(
+            r#"
+            [[command_sensors]]
+            id = "foo"
+            name = "Foo"
+            command = ["true"]
+            [[file_sensors]]
+            id = "bar"
+            name = "Bar"
+            path = "/bar"
+            [[commands]]
+            id = "foo"
+            name = "Foo"
+            command = ["true"]
+            "#,
+        );
+        assert!(check_unique_ids(&config).is_ok());
+
+        // Command and file sensors with IDs differing only in case.
+        let config = parse_config(
+            r#"
+            [[command_sensors]]
+            id = "Foo"
+            name = "Foo"
+            command = ["true"]
+            [[file_sensors]]
+            id = "foo"
+            name = "Foo"
+            path = "/foo"
+            "#,
+        );
+        assert!(check_unique_ids(&config).is_err());
+
+        // Commands with the same snake case ID.
+        let config = parse_config(
+            r#"
+            [[commands]]
+            id = "foo-bar"
+            name = "Foo"
+            command = ["true"]
+            [[commands]]
+            id = "foo_bar"
+            name = "Foo"
+            command = ["true"]
+            "#,
+        );
+        assert!(check_unique_ids(&config).is_err());
+
+        // A sensor taking the ID of the result sensor of a command.
+        let config = parse_config(
+            r#"
+            [[file_sensors]]
+            id = "foo_result"
+            name = "Foo"
+            path = "/foo"
+            [[commands]]
+            id = "foo"
+            name = "Foo"
+            command = ["true"]
+            "#,
+        );
+        assert!(check_unique_ids(&config).is_err());
+    }
+}
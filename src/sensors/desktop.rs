@@ -0,0 +1,121 @@
+use crate::ha::values::{DeviceClass, EntityCategory};
+use crate::sensor::{Sensor, SensorDiscovery, SensorDiscoveryInit};
+use crate::utils::logind;
+use crate::utils::serialize::serialize_timestamp_opt;
+use anyhow::{Context, Error};
+use serde::Serialize;
+use std::sync::Arc;
+use time::OffsetDateTime;
+use tokio::sync::Notify;
+use zbus::Connection;
+
+const ID: &str = "desktop";
+
+pub struct DesktopSensor {
+    topic: Box<str>,
+    connection: Connection,
+    changed: Arc<Notify>,
+}
+
+impl DesktopSensor {
+    pub fn new(topic_base: &str, connection: &Connection) -> Self {
+        let topic = format!("{topic_base}/{ID}").into_boxed_str();
+        let changed = Arc::new(Notify::new());
+        logind::notify_on_property_changes(
+            connection,
+            &["LockedHint", "IdleHint", "ActiveSession"],
+            changed.clone(),
+        );
+        DesktopSensor {
+            topic,
+            connection: connection.clone(),
+            changed,
+        }
+    }
+
+    async fn read_locked(&self) -> Result<bool, Error> {
+        let Some(session) = logind::active_session_proxy(&self.connection).await? else {
+            // Nobody is at the screen.
+            return Ok(true);
+        };
+        let class: String = session.get_property("Class").await.context("Class")?;
+        // Greeters are shown when all users are logged out or switching users.
+        if class != "user" {
+            return Ok(true);
+        }
+        session
+            .get_property("LockedHint")
+            .await
+            .context("LockedHint")
+    }
+}
+
+impl Sensor for DesktopSensor {
+    type Payload = Payload;
+
+    fn topic(&self) -> &str {
+        self.topic.as_ref()
+    }
+
+    fn discovery_data(&self) -> Vec<SensorDiscovery<'_>> {
+        vec![
+            SensorDiscovery::new(SensorDiscoveryInit {
+                id: "screen_locked",
+                title: "Screen locked",
+                icon: "mdi:monitor-lock",
+                value_template: "{{ 'ON' if value_json.locked else 'OFF' }}",
+            })
+            .with_binary(true),
+            SensorDiscovery::new(SensorDiscoveryInit {
+                id: "idle",
+                title: "Idle",
+                icon: "mdi:account-clock",
+                value_template: "{{ 'ON' if value_json.idle else 'OFF' }}",
+            })
+            .with_binary(true),
+            SensorDiscovery::new(SensorDiscoveryInit {
+                id: "idle_since",
+                title: "Idle since",
+                icon: "mdi:account-clock",
+                value_template: "{{ value_json.idle_since }}",
+            })
+            .with_entity_category(EntityCategory::Diagnostic)
+            .with_device_class(DeviceClass::Timestamp),
+        ]
+    }
+
+    async fn get_status(&self) -> Result<Self::Payload, Error> {
+        let locked = self.read_locked().await?;
+        // The manager is idle only when all sessions are.
+        let manager = logind::manager_proxy(&self.connection).await?;
+        let idle: bool = manager.get_property("IdleHint").await.context("IdleHint")?;
+        let idle_since = match idle {
+            true => {
+                let micros: u64 = manager
+                    .get_property("IdleSinceHint")
+                    .await
+                    .context("IdleSinceHint")?;
+                OffsetDateTime::from_unix_timestamp_nanos(i128::from(micros) * 1000).ok()
+            }
+            false => None,
+        };
+        Ok(Payload {
+            locked,
+            idle,
+            idle_since,
+        })
+    }
+
+    fn trigger(&self) -> Option<&Notify> {
+        Some(&self.changed)
+    }
+}
+
+#[derive(Serialize)]
+pub struct Payload {
+    /// Whether the screen is locked, or shows no session of any user.
+    locked: bool,
+    idle: bool,
+    #[serde(serialize_with = "serialize_timestamp_opt")]
+    idle_since: Option<OffsetDateTime>,
+}
@@ -1,19 +1,26 @@
 use crate::ha::values::{DeviceClass, EntityCategory, StateClass};
 use crate::sensor::{Sensor, SensorDiscovery, SensorDiscoveryInit};
+use crate::utils::parser::parse_next_field;
 use anyhow::{Context, Error, ensure};
 use serde::Serialize;
 use std::fs;
+use std::path::Path;
 
 const ID: &str = "memory";
 
+const BLOCK_DIR: &str = "/sys/block";
+
 pub struct MemorySensor {
     topic: Box<str>,
+    /// Whether any zram device exists.
+    has_zram: bool,
 }
 
 impl MemorySensor {
     pub fn new(topic_base: &str) -> Self {
         let topic = format!("{topic_base}/{ID}").into_boxed_str();
-        MemorySensor { topic }
+        let has_zram = list_zram_devices().is_ok_and(|devices| !devices.is_empty());
+        MemorySensor { topic, has_zram }
     }
 }
 
@@ -35,7 +42,7 @@ impl Sensor for MemorySensor {
         .with_state_class(StateClass::Measurement)
         .with_entity_category(EntityCategory::Diagnostic)
         .with_unit_of_measurement("KiB");
-        vec![
+        let mut data = vec![
             SensorDiscovery {
                 id: "memory_use".into(),
                 title: "Memory use".into(),
@@ -61,7 +68,48 @@ impl Sensor for MemorySensor {
                 value_template: "{{ value_json.swap_free }}".into(),
                 ..base_discovery
             },
-        ]
+        ];
+        for (id, title) in [
+            ("cached", "cached"),
+            ("buffers", "buffers"),
+            ("shared", "shared"),
+            ("slab", "slab"),
+            ("dirty", "dirty"),
+            ("hugepages", "huge pages"),
+        ] {
+            data.push(SensorDiscovery {
+                id: format!("memory_{id}").into(),
+                title: format!("Memory {title}").into(),
+                value_template: format!("{{{{ value_json.{id} }}}}").into(),
+                ..base_discovery
+            });
+        }
+        data.push(SensorDiscovery {
+            id: "zswap_use".into(),
+            title: "Zswap use".into(),
+            value_template: "{{ value_json.zswap_use }}".into(),
+            attributes_template: Some("{{ {'zswapped': value_json.zswapped} | tojson }}"),
+            ..base_discovery
+        });
+        if self.has_zram {
+            data.push(SensorDiscovery {
+                id: "zram_use".into(),
+                title: "Zram use".into(),
+                value_template: "{{ value_json.zram_use }}".into(),
+                attributes_template: Some("{{ {'zram_stored': value_json.zram_stored} | tojson }}"),
+                ..base_discovery
+            });
+        }
+        data.push(
+            SensorDiscovery::new(SensorDiscoveryInit {
+                id: "oom_kills",
+                title: "OOM kills",
+                icon: "mdi:skull-crossbones",
+                value_template: "{{ value_json.oom_kills }}",
+            })
+            .with_state_class(StateClass::TotalIncreasing),
+        );
+        data
     }
 
     async fn get_status(&self) -> Result<Self::Payload, Error> {
@@ -72,7 +120,23 @@ impl Sensor for MemorySensor {
             mem_available,
             swap_total,
             swap_free,
+            cached,
+            buffers,
+            shared,
+            slab,
+            dirty,
+            hugepages_total,
+            hugepages_free,
+            hugepage_size,
+            zswap,
+            zswapped,
         } = parse_mem_info(&mem_info).context("Failed to parse mem info")?;
+        let vm_stat = fs::read_to_string("/proc/vmstat").context("Failed to read vmstat")?;
+        let VmStat { oom_kill, .. } = parse_vm_stat(&vm_stat).context("Failed to parse vmstat")?;
+        let zram = match self.has_zram {
+            true => Some(read_zram().context("Failed to read zram stats")?),
+            false => None,
+        };
         // This logic is mimicking what is used in `free` command:
         // https://gitlab.com/procps-ng/procps/-/blob/2cded082b86ff6ee82174cf9f48449797695847d/library/meminfo.c#L739
         let mem_used = if mem_available > 0 && mem_available < mem_total {
@@ -85,6 +149,17 @@ impl Sensor for MemorySensor {
             mem_free: mem_free / 1024,
             swap_use: (swap_total - swap_free) / 1024,
             swap_free: swap_free / 1024,
+            cached: cached / 1024,
+            buffers: buffers / 1024,
+            shared: shared / 1024,
+            slab: slab / 1024,
+            dirty: dirty / 1024,
+            hugepages: (hugepages_total - hugepages_free) * hugepage_size / 1024,
+            zswap_use: zswap / 1024,
+            zswapped: zswapped / 1024,
+            zram_use: zram.as_ref().map(|zram| zram.mem_used / 1024),
+            zram_stored: zram.as_ref().map(|zram| zram.orig_data_size / 1024),
+            oom_kills: oom_kill,
         })
     }
 }
@@ -95,6 +170,24 @@ pub struct Payload {
     mem_free: u64,
     swap_use: u64,
     swap_free: u64,
+    cached: u64,
+    buffers: u64,
+    shared: u64,
+    slab: u64,
+    dirty: u64,
+    /// Memory of huge pages in use.
+    hugepages: u64,
+    /// Compressed size of pages stored in zswap.
+    zswap_use: u64,
+    /// Original size of pages stored in zswap.
+    zswapped: u64,
+    /// Memory used by zram devices.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    zram_use: Option<u64>,
+    /// Original size of data stored in zram devices.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    zram_stored: Option<u64>,
+    oom_kills: u64,
 }
 
 fn parse_mem_info(mem_info: &str) -> Result<MemInfo, Error> {
@@ -103,6 +196,16 @@ fn parse_mem_info(mem_info: &str) -> Result<MemInfo, Error> {
     let mut mem_available = 0;
     let mut swap_total = 0;
     let mut swap_free = 0;
+    let mut cached = 0;
+    let mut buffers = 0;
+    let mut shared = 0;
+    let mut slab = 0;
+    let mut dirty = 0;
+    let mut hugepages_total = 0;
+    let mut hugepages_free = 0;
+    let mut hugepage_size = 0;
+    let mut zswap = 0;
+    let mut zswapped = 0;
     for line in mem_info.lines() {
         let Some((label, value)) = line.split_once(':') else {
             continue;
@@ -113,6 +216,16 @@ fn parse_mem_info(mem_info: &str) -> Result<MemInfo, Error> {
             "MemAvailable" => &mut mem_available,
             "SwapTotal" => &mut swap_total,
             "SwapFree" => &mut swap_free,
+            "Cached" => &mut cached,
+            "Buffers" => &mut buffers,
+            "Shmem" => &mut shared,
+            "Slab" => &mut slab,
+            "Dirty" => &mut dirty,
+            "HugePages_Total" => &mut hugepages_total,
+            "HugePages_Free" => &mut hugepages_free,
+            "Hugepagesize" => &mut hugepage_size,
+            "Zswap" => &mut zswap,
+            "Zswapped" => &mut zswapped,
             _ => continue,
         };
         *slot = parse_value(value).with_context(|| format!("Failed to parse {label}"))?;
@@ -123,6 +236,16 @@ fn parse_mem_info(mem_info: &str) -> Result<MemInfo, Error> {
         mem_available,
         swap_total,
         swap_free,
+        cached,
+        buffers,
+        shared,
+        slab,
+        dirty,
+        hugepages_total,
+        hugepages_free,
+        hugepage_size,
+        zswap,
+        zswapped,
     })
 }
 
@@ -133,6 +256,82 @@ struct MemInfo {
     mem_available: u64,
     swap_total: u64,
     swap_free: u64,
+    cached: u64,
+    buffers: u64,
+    shared: u64,
+    slab: u64,
+    dirty: u64,
+    /// Number of huge pages.
+    hugepages_total: u64,
+    hugepages_free: u64,
+    hugepage_size: u64,
+    zswap: u64,
+    zswapped: u64,
+}
+
+/// Counters from `/proc/vmstat`.
+#[derive(Debug, Default, Eq, PartialEq)]
+pub(super) struct VmStat {
+    pub oom_kill: u64,
+    /// Pages swapped in.
+    pub pswpin: u64,
+    /// Pages swapped out.
+    pub pswpout: u64,
+}
+
+pub(super) fn parse_vm_stat(vm_stat: &str) -> Result<VmStat, Error> {
+    let mut result = VmStat::default();
+    for line in vm_stat.lines() {
+        let Some((label, value)) = line.split_once(' ') else {
+            continue;
+        };
+        let slot = match label {
+            "oom_kill" => &mut result.oom_kill,
+            "pswpin" => &mut result.pswpin,
+            "pswpout" => &mut result.pswpout,
+            _ => continue,
+        };
+        *slot = value
+            .parse()
+            .with_context(|| format!("Failed to parse {label}"))?;
+    }
+    Ok(result)
+}
+
+fn list_zram_devices() -> Result<Vec<String>, Error> {
+    let mut devices = Vec::new();
+    for entry in fs::read_dir(BLOCK_DIR).context("Failed to list block devices")? {
+        let name = entry?.file_name().to_string_lossy().into_owned();
+        if name.starts_with("zram") {
+            devices.push(name);
+        }
+    }
+    Ok(devices)
+}
+
+#[derive(Default)]
+struct ZramStat {
+    orig_data_size: u64,
+    mem_used: u64,
+}
+
+/// Sum up stats of all zram devices.
+fn read_zram() -> Result<ZramStat, Error> {
+    let mut result = ZramStat::default();
+    for device in list_zram_devices()? {
+        let path = Path::new(BLOCK_DIR).join(&device).join("mm_stat");
+        // Devices not initialized yet have nothing to read.
+        let Ok(mm_stat) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let mut fields = mm_stat.split_ascii_whitespace();
+        let orig_data_size: u64 = parse_next_field(&mut fields).context("orig_data_size")?;
+        let _compr_data_size: u64 = parse_next_field(&mut fields).context("compr_data_size")?;
+        let mem_used: u64 = parse_next_field(&mut fields).context("mem_used_total")?;
+        result.orig_data_size += orig_data_size;
+        result.mem_used += mem_used;
+    }
+    Ok(result)
 }
 
 fn parse_value(s: &str) -> Result<u64, Error> {
@@ -151,7 +350,7 @@ fn parse_value(s: &str) -> Result<u64, Error> {
 
 #[cfg(test)]
 mod tests {
-    use super::{MemInfo, parse_mem_info};
+    use super::{MemInfo, VmStat, parse_mem_info, parse_vm_stat};
 
     #[test]
     fn test_parse_mem_info() {
@@ -164,6 +363,29 @@ mod tests {
                 mem_available: 52_700_668 * 1024,
                 swap_total: 6_040_576 * 1024,
                 swap_free: 6_040_576 * 1024,
+                cached: 19_555_260 * 1024,
+                buffers: 5_292 * 1024,
+                shared: 2_923_772 * 1024,
+                slab: 1_883_808 * 1024,
+                dirty: 1_344 * 1024,
+                hugepages_total: 0,
+                hugepages_free: 0,
+                hugepage_size: 2_048 * 1024,
+                zswap: 0,
+                zswapped: 0,
+            },
+        );
+    }
+
+    #[test]
+    fn test_parse_vm_stat() {
+        let vm_stat = include_str!("assets/vmstat_1");
+        assert_eq!(
+            parse_vm_stat(vm_stat).unwrap(),
+            VmStat {
+                oom_kill: 2,
+                pswpin: 1523,
+                pswpout: 4871,
             },
         );
     }
@@ -1,51 +1,217 @@
 use self::apt::AptSensor;
+use self::apt_job::AptJobSensor;
 use self::cpu::CpuSensor;
+use self::cpufreq::CpuFreqSensor;
+use self::custom::command::CustomCommandSensor;
+use self::custom::command_result::CustomCommandResultSensor;
+use self::custom::file::CustomFileSensor;
+use self::desktop::DesktopSensor;
 use self::memory::MemorySensor;
 use self::monitor::MonitorSensor;
+use self::os::OsSensor;
+use self::power_state::PowerStateSensor;
+use self::pressure::PressureSensor;
+use self::rapl::RaplSensor;
 use self::reboot::RebootSensor;
+use self::restart::RestartSensor;
+use self::resume::ResumeSensor;
+use self::sessions::SessionsSensor;
+use self::snap::SnapSensor;
+use self::swap::SwapSensor;
+use self::switch::SwitchSensor;
+use self::unattended_upgrades::UnattendedUpgradesSensor;
+use self::uptime::UptimeSensor;
+use crate::commands::Commands;
+use crate::config::Config;
 use crate::sensors::disk::DiskSensor;
 use crate::sensors::load::LoadSensor;
 use crate::sensors::net::NetSensor;
 use anyhow::{Context, Error};
+use zbus::Connection;
+
+pub(crate) use self::apt::ID as APT_ID;
+pub(crate) use self::cpufreq::ID as CPUFREQ_ID;
+pub use self::power_state::PowerState;
 
 mod apt;
+mod apt_job;
 mod cpu;
+mod cpufreq;
+mod custom;
+mod desktop;
 mod disk;
 mod load;
 mod memory;
 mod monitor;
 mod net;
+mod os;
+mod power_state;
+mod pressure;
+mod rapl;
 mod reboot;
+mod restart;
+mod resume;
+mod sampler;
+mod sessions;
+mod snap;
+mod swap;
+mod switch;
+mod unattended_upgrades;
+mod uptime;
+
+/// Topic under the topic base which sensors publish to.
+const TOPIC: &str = "sensor";
 
 pub struct Sensors {
     pub monitor_sensor: MonitorSensor,
     pub cpu_sensor: CpuSensor,
+    /// Present only if CPU frequency scaling is available.
+    pub cpufreq_sensor: Option<CpuFreqSensor>,
     pub memory_sensor: MemorySensor,
+    pub swap_sensor: SwapSensor,
     pub disk_sensor: DiskSensor,
     pub load_sensor: LoadSensor,
+    /// Present only if pressure stall information is available.
+    pub pressure_sensor: Option<PressureSensor>,
+    /// Present only if RAPL energy counters are available and readable.
+    pub rapl_sensor: Option<RaplSensor>,
     pub net_sensor: NetSensor,
+    pub os_sensor: OsSensor,
+    pub uptime_sensor: UptimeSensor,
+    pub sessions_sensor: SessionsSensor,
+    /// Present only if enabled in config.
+    pub desktop_sensor: Option<DesktopSensor>,
     pub apt_sensor: AptSensor,
+    pub apt_job_sensor: AptJobSensor,
+    /// Present only if snapd is available.
+    pub snap_sensor: Option<SnapSensor>,
+    pub unattended_upgrades_sensor: UnattendedUpgradesSensor,
     pub reboot_sensor: RebootSensor,
+    pub restart_sensor: RestartSensor,
+    pub resume_sensor: ResumeSensor,
+    pub power_state_sensor: PowerStateSensor,
+    pub custom_command_sensors: Vec<CustomCommandSensor>,
+    pub custom_file_sensors: Vec<CustomFileSensor>,
+    pub custom_command_result_sensors: Vec<CustomCommandResultSensor>,
+    pub switch_sensors: Vec<SwitchSensor>,
+}
+
+impl Sensors {
+    /// Get sensors ready to publish fresh values right after resuming from suspend.
+    pub fn prepare_resume(&self) {
+        self.cpu_sensor.resample();
+        self.net_sensor.resample();
+        self.swap_sensor.resample();
+        if let Some(sensor) = &self.rapl_sensor {
+            sensor.resample();
+        }
+        self.resume_sensor.record_resume();
+    }
+}
+
+/// Topic of a sensor, for entities of commands which take their state from it.
+pub fn sensor_topic(topic_base: &str, id: &str) -> String {
+    format!("{topic_base}/{TOPIC}/{id}")
 }
 
-pub fn create_sensors(topic_base: &str) -> Result<Sensors, Error> {
-    let topic_base = format!("{topic_base}/sensor");
+pub fn create_sensors(
+    topic_base: &str,
+    config: &Config,
+    commands: &Commands,
+    connection: &Connection,
+) -> Result<Sensors, Error> {
+    custom::check_unique_ids(config)?;
+    let topic_base = format!("{topic_base}/{TOPIC}");
     let monitor_sensor = MonitorSensor::new(&topic_base);
-    let cpu_sensor = CpuSensor::new(&topic_base).context("Failed to create CPU sensor")?;
+    let cpu_sensor =
+        CpuSensor::new(&topic_base, &config.cpu).context("Failed to create CPU sensor")?;
+    let cpufreq_sensor = CpuFreqSensor::new(
+        &topic_base,
+        commands
+            .governor_command
+            .as_ref()
+            .map(|command| command.changed()),
+    );
     let memory_sensor = MemorySensor::new(&topic_base);
+    let swap_sensor = SwapSensor::new(&topic_base).context("Failed to create swap sensor")?;
     let disk_sensor = DiskSensor::new(&topic_base);
     let load_sensor = LoadSensor::new(&topic_base);
+    let pressure_sensor = PressureSensor::new(&topic_base, &config.pressure)
+        .context("Failed to create pressure sensor")?;
+    let rapl_sensor = RaplSensor::new(&topic_base).context("Failed to create RAPL sensor")?;
     let net_sensor = NetSensor::new(&topic_base).context("Failed to create network sensor")?;
-    let apt_sensor = AptSensor::new(&topic_base);
-    let reboot_sensor = RebootSensor::new(&topic_base);
+    let os_sensor = OsSensor::new(&topic_base);
+    let uptime_sensor = UptimeSensor::new(&topic_base);
+    let sessions_sensor = SessionsSensor::new(&topic_base, connection);
+    let desktop_sensor = config
+        .desktop
+        .enabled
+        .then(|| DesktopSensor::new(&topic_base, connection));
+    let apt_jobs = commands.apt_upgrade_command.jobs();
+    let apt_sensor = AptSensor::new(&topic_base, apt_jobs.clone());
+    let apt_job_sensor = AptJobSensor::new(&topic_base, apt_jobs.clone());
+    let reboot_sensor = RebootSensor::new(&topic_base, apt_jobs);
+    let restart_sensor =
+        RestartSensor::new(&topic_base, commands.restart_services_command.restarted());
+    let unattended_upgrades_sensor = UnattendedUpgradesSensor::new(&topic_base);
+    let snap_sensor = commands
+        .snap_refresh_command
+        .as_ref()
+        .map(|command| SnapSensor::new(&topic_base, command.refreshed()));
+    let resume_sensor = ResumeSensor::new(&topic_base);
+    let power_state_sensor = PowerStateSensor::new(&topic_base);
+    let custom_command_sensors = config
+        .command_sensors
+        .iter()
+        .map(|config| {
+            CustomCommandSensor::new(&topic_base, config)
+                .with_context(|| format!("Failed to create command sensor {}", config.id))
+        })
+        .collect::<Result<_, _>>()?;
+    let custom_file_sensors = config
+        .file_sensors
+        .iter()
+        .map(|config| {
+            CustomFileSensor::new(&topic_base, config)
+                .with_context(|| format!("Failed to create file sensor {}", config.id))
+        })
+        .collect::<Result<_, _>>()?;
+    let custom_command_result_sensors = commands
+        .custom_commands
+        .iter()
+        .map(|command| CustomCommandResultSensor::new(&topic_base, command))
+        .collect();
+    let switch_sensors = commands
+        .switch_commands
+        .iter()
+        .map(|command| SwitchSensor::new(command.state()))
+        .collect();
     Ok(Sensors {
         monitor_sensor,
         cpu_sensor,
+        cpufreq_sensor,
         memory_sensor,
+        swap_sensor,
         disk_sensor,
         load_sensor,
+        pressure_sensor,
+        rapl_sensor,
         net_sensor,
+        os_sensor,
+        uptime_sensor,
+        sessions_sensor,
+        desktop_sensor,
         apt_sensor,
+        apt_job_sensor,
+        snap_sensor,
+        unattended_upgrades_sensor,
         reboot_sensor,
+        restart_sensor,
+        resume_sensor,
+        power_state_sensor,
+        custom_command_sensors,
+        custom_file_sensors,
+        custom_command_result_sensors,
+        switch_sensors,
     })
 }
@@ -1,10 +1,9 @@
 use crate::ha::values::{DeviceClass, EntityCategory};
 use crate::sensor::{Sensor, SensorDiscovery, SensorDiscoveryInit};
+use crate::utils::serialize::serialize_timestamp;
 use anyhow::Error;
-use serde::ser::Error as _;
-use serde::{Serialize, Serializer};
+use serde::Serialize;
 use time::OffsetDateTime;
-use time::format_description::well_known::Iso8601;
 
 const ID: &str = "monitor";
 
@@ -46,14 +45,4 @@ impl Sensor for MonitorSensor {
 
 #[derive(Serialize)]
 #[serde(transparent)]
-pub struct Payload(#[serde(serialize_with = "serialize")] OffsetDateTime);
-
-pub fn serialize<S: Serializer>(
-    datetime: &OffsetDateTime,
-    serializer: S,
-) -> Result<S::Ok, S::Error> {
-    datetime
-        .format(&Iso8601::DEFAULT)
-        .map_err(S::Error::custom)?
-        .serialize(serializer)
-}
+pub struct Payload(#[serde(serialize_with = "serialize_timestamp")] OffsetDateTime);
@@ -1,74 +1,79 @@
+use super::sampler::{Sample, Sampler};
 use crate::ha::values::{DeviceClass, StateClass};
 use crate::sensor::{Sensor, SensorDiscovery, SensorDiscoveryInit};
 use anyhow::{Context, Error, bail};
 use serde::ser::SerializeMap;
 use serde::{Serialize, Serializer};
 use std::fs;
-use std::time::{Duration, Instant};
-use tokio::sync::{Mutex, watch};
+use std::time::Duration;
 
 const ID: &str = "net";
 
 pub struct NetSensor {
     topic: Box<str>,
     interfaces: Vec<String>,
-    rx: Mutex<watch::Receiver<Payload>>,
+    sampler: Sampler<Payload>,
 }
 
 impl NetSensor {
     pub fn new(topic_base: &str) -> Result<Self, Error> {
         let topic = format!("{topic_base}/{ID}").into_boxed_str();
-        let mut last_obs = DeviceStatusObservation::new()?;
-        let interfaces = last_obs
-            .observation
+        let first = read_device_status()?;
+        let interfaces = first
             .iter()
             .filter(|s| !s.is_lo())
             .map(|s| s.interface.clone())
             .collect();
-        let (tx, rx) = watch::channel(Default::default());
-        tokio::spawn(async move {
-            loop {
-                tokio::time::sleep(Duration::from_secs(60)).await;
-                let obs = DeviceStatusObservation::new()?;
-                let duration = obs.timestamp - last_obs.timestamp;
-                let stats = obs
-                    .observation
-                    .iter()
-                    .filter(|s| !s.is_lo())
-                    .filter_map(|status| {
-                        let last_status = last_obs
-                            .observation
-                            .iter()
-                            .find(|s| s.interface == status.interface)?;
-                        let calc = |f: fn(&DeviceStatus) -> u64| {
-                            ((f(status) - f(last_status)) as f32 / duration.as_secs_f32()).round()
-                        };
-                        let bytes_in = calc(|s| s.receive_bytes);
-                        let bytes_out = calc(|s| s.transmit_bytes);
-                        Some((
-                            status.interface.clone(),
-                            InterfaceStat {
-                                bytes_in,
-                                bytes_out,
-                            },
-                        ))
-                    })
-                    .collect();
-                tx.send(Payload(stats))
-                    .context("Failed to update payload")?;
-                last_obs = obs;
-            }
-
-            // This is to specify the return type so that `?` may be used above.
-            #[allow(unreachable_code)]
-            Ok::<(), Error>(())
-        });
         Ok(NetSensor {
             topic,
             interfaces,
-            rx: Mutex::new(rx),
+            sampler: Sampler::start(NetSample, first),
         })
     }
+
+    pub fn resample(&self) {
+        self.sampler.resample();
+    }
+}
+
+/// Average throughput of network interfaces over the sample.
+struct NetSample;
+
+impl Sample for NetSample {
+    type Observation = Vec<DeviceStatus>;
+    type Payload = Payload;
+
+    fn observe(&self) -> Result<Vec<DeviceStatus>, Error> {
+        read_device_status()
+    }
+
+    fn calculate(
+        &mut self,
+        start: &Vec<DeviceStatus>,
+        end: &Vec<DeviceStatus>,
+        duration: Duration,
+    ) -> Payload {
+        let stats = end
+            .iter()
+            .filter(|s| !s.is_lo())
+            .filter_map(|status| {
+                let last_status = start.iter().find(|s| s.interface == status.interface)?;
+                let calc = |f: fn(&DeviceStatus) -> u64| {
+                    ((f(status) - f(last_status)) as f32 / duration.as_secs_f32()).round()
+                };
+                let bytes_in = calc(|s| s.receive_bytes);
+                let bytes_out = calc(|s| s.transmit_bytes);
+                Some((
+                    status.interface.clone(),
+                    InterfaceStat {
+                        bytes_in,
+                        bytes_out,
+                    },
+                ))
+            })
+            .collect();
+        Payload(stats)
+    }
 }
 
 impl Sensor for NetSensor {
@@ -107,10 +112,7 @@ impl Sensor for NetSensor {
     }
 
     async fn get_status(&self) -> Result<Self::Payload, Error> {
-        let mut rx = self.rx.try_lock().context("Failed to acquire receiver")?;
-        rx.changed().await.context("Failed to wait for receiver")?;
-        let payload = rx.borrow_and_update().clone();
-        Ok(payload)
+        self.sampler.next().await
     }
 }
 
@@ -136,21 +138,9 @@ where
     map.end()
 }
 
-struct DeviceStatusObservation {
-    timestamp: Instant,
-    observation: Vec<DeviceStatus>,
-}
-
-impl DeviceStatusObservation {
-    fn new() -> Result<Self, Error> {
-        let timestamp = Instant::now();
-        let dev = fs::read_to_string("/proc/net/dev").context("Failed to read /proc/net/dev")?;
-        let observation = parse_device_status(&dev).context("Failed to parse /proc/net/dev")?;
-        Ok(Self {
-            timestamp,
-            observation,
-        })
-    }
+fn read_device_status() -> Result<Vec<DeviceStatus>, Error> {
+    let dev = fs::read_to_string("/proc/net/dev").context("Failed to read /proc/net/dev")?;
+    parse_device_status(&dev).context("Failed to parse /proc/net/dev")
 }
 
 fn parse_device_status(s: &str) -> Result<Vec<DeviceStatus>, Error> {
@@ -0,0 +1,257 @@
+use crate::ha::values::{DeviceClass, EntityCategory};
+use crate::host::os_release::{self, OsRelease};
+use crate::sensor::{Sensor, SensorDiscovery, SensorDiscoveryInit};
+use anyhow::{Context, Error};
+use log::warn;
+use nix::sys::utsname::uname;
+use serde::Serialize;
+use std::fs;
+use time::{Date, Month, OffsetDateTime};
+
+const ID: &str = "os";
+
+const DISTRO_INFO_FILE: &str = "/usr/share/distro-info/ubuntu.csv";
+const BOOT_DIR: &str = "/boot";
+
+pub struct OsSensor {
+    topic: Box<str>,
+}
+
+impl OsSensor {
+    pub fn new(topic_base: &str) -> Self {
+        let topic = format!("{topic_base}/{ID}").into_boxed_str();
+        OsSensor { topic }
+    }
+}
+
+impl Sensor for OsSensor {
+    type Payload = Payload;
+
+    fn topic(&self) -> &str {
+        self.topic.as_ref()
+    }
+
+    fn discovery_data(&self) -> Vec<SensorDiscovery<'_>> {
+        vec![
+            SensorDiscovery::new(SensorDiscoveryInit {
+                id: "os_release",
+                title: "OS release",
+                icon: "mdi:ubuntu",
+                value_template: "{{ value_json.release.pretty_name }}",
+            })
+            .with_entity_category(EntityCategory::Diagnostic)
+            .with_attributes("{{ value_json.release | tojson }}"),
+            SensorDiscovery::new(SensorDiscoveryInit {
+                id: "os_support_end",
+                title: "OS end of standard support",
+                icon: "mdi:calendar-end",
+                value_template: "{{ value_json.support.end }}",
+            })
+            .with_entity_category(EntityCategory::Diagnostic)
+            .with_device_class(DeviceClass::Date)
+            .with_attributes("{{ value_json.support | tojson }}"),
+            SensorDiscovery::new(SensorDiscoveryInit {
+                id: "os_unsupported",
+                title: "OS release unsupported",
+                icon: "mdi:calendar-alert",
+                value_template: "{{ 'ON' if value_json.support.ended else 'OFF' }}",
+            })
+            .with_binary(true)
+            .with_device_class(DeviceClass::Problem),
+            SensorDiscovery::new(SensorDiscoveryInit {
+                id: "kernel",
+                title: "Kernel",
+                icon: "mdi:penguin",
+                value_template: "{{ value_json.kernel.running }}",
+            })
+            .with_entity_category(EntityCategory::Diagnostic)
+            .with_attributes("{{ value_json.kernel | tojson }}"),
+            SensorDiscovery::new(SensorDiscoveryInit {
+                id: "kernel_newer",
+                title: "Newer kernel installed",
+                icon: "mdi:penguin",
+                value_template: "{{ 'ON' if value_json.kernel.newer_installed else 'OFF' }}",
+            })
+            .with_binary(true)
+            .with_device_class(DeviceClass::Update),
+        ]
+    }
+
+    async fn get_status(&self) -> Result<Self::Payload, Error> {
+        let OsRelease {
+            id,
+            pretty_name,
+            version_id,
+            codename,
+        } = os_release::get()?;
+        let support = match (id.as_deref(), &codename) {
+            (Some("ubuntu"), Some(codename)) => read_support(codename).unwrap_or_else(|e| {
+                warn!("Failed to read support status: {e}");
+                Support::default()
+            }),
+            _ => Support::default(),
+        };
+        let running = uname()
+            .context("Failed to get kernel release")?
+            .release()
+            .to_string_lossy()
+            .into_owned();
+        let installed = list_installed_kernels().unwrap_or_else(|e| {
+            warn!("Failed to list installed kernels: {e}");
+            vec![]
+        });
+        let latest = find_latest_kernel(&running, &installed).to_owned();
+        Ok(Payload {
+            release: Release {
+                pretty_name,
+                version_id,
+                codename,
+            },
+            support,
+            kernel: Kernel {
+                newer_installed: latest != running,
+                running,
+                latest,
+            },
+        })
+    }
+}
+
+#[derive(Serialize)]
+pub struct Payload {
+    release: Release,
+    support: Support,
+    kernel: Kernel,
+}
+
+#[derive(Serialize)]
+struct Release {
+    pretty_name: Option<String>,
+    version_id: Option<String>,
+    codename: Option<String>,
+}
+
+#[derive(Default, Serialize)]
+struct Support {
+    /// End of standard support.
+    end: Option<String>,
+    /// End of Expanded Security Maintenance.
+    esm_end: Option<String>,
+    /// Whether standard support has ended.
+    ended: bool,
+}
+
+#[derive(Serialize)]
+struct Kernel {
+    running: String,
+    /// Latest installed kernel of the same flavour as the running one.
+    latest: String,
+    newer_installed: bool,
+}
+
+fn read_support(codename: &str) -> Result<Support, Error> {
+    let csv = fs::read_to_string(DISTRO_INFO_FILE).context("Failed to read distro-info")?;
+    let support = parse_support(&csv, codename).context("Release not found in distro-info")?;
+    Ok(support)
+}
+
+/// Find the support dates of a release from the distro-info CSV.
+fn parse_support(csv: &str, codename: &str) -> Option<Support> {
+    let mut lines = csv.lines();
+    let header = lines.next()?.split(',').collect::<Vec<_>>();
+    let column = |name: &str| header.iter().position(|&h| h == name);
+    let (series, eol, eol_esm) = (column("series")?, column("eol")?, column("eol-esm"));
+    let row = lines
+        .map(|line| line.split(',').collect::<Vec<_>>())
+        .find(|row| row.get(series) == Some(&codename))?;
+    let field = |i: usize| row.get(i).filter(|s| !s.is_empty()).map(|&s| s.to_owned());
+    let end = field(eol);
+    let ended = end
+        .as_deref()
+        .and_then(parse_date)
+        .is_some_and(|end| end < OffsetDateTime::now_utc().date());
+    Some(Support {
+        end,
+        esm_end: eol_esm.and_then(field),
+        ended,
+    })
+}
+
+fn parse_date(s: &str) -> Option<Date> {
+    let mut parts = s.splitn(3, '-').map(|part| part.parse::<u16>().ok());
+    let year = parts.next()??;
+    let month = Month::try_from(parts.next()?? as u8).ok()?;
+    let day = parts.next()??;
+    Date::from_calendar_date(year as i32, month, day as u8).ok()
+}
+
+/// List releases of kernels installed in `/boot`, e.g. `6.8.0-45-generic`.
+fn list_installed_kernels() -> Result<Vec<String>, Error> {
+    let mut kernels = Vec::new();
+    for entry in fs::read_dir(BOOT_DIR).context("Failed to read /boot")? {
+        let name = entry?.file_name();
+        if let Some(release) = name.to_str().and_then(|n| n.strip_prefix("vmlinuz-")) {
+            kernels.push(release.to_owned());
+        }
+    }
+    Ok(kernels)
+}
+
+/// Find the latest kernel with the same flavour as the running one, e.g. `generic`.
+fn find_latest_kernel<'a>(running: &'a str, installed: &'a [String]) -> &'a str {
+    let flavour = |release: &'a str| release.trim_start_matches(|c: char| !c.is_alphabetic());
+    installed
+        .iter()
+        .map(String::as_str)
+        .filter(|release| flavour(release) == flavour(running))
+        .chain([running])
+        .max_by_key(|release| version_key(release))
+        .unwrap()
+}
+
+/// Numbers in a kernel release for comparison, e.g. `[6, 8, 0, 45]` for `6.8.0-45-generic`.
+fn version_key(release: &str) -> Vec<u64> {
+    release
+        .split(|c: char| !c.is_ascii_digit())
+        .filter_map(|part| part.parse().ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_latest_kernel, parse_support};
+
+    #[test]
+    fn test_parse_support() {
+        let csv = include_str!("assets/distro_info_ubuntu_1");
+        let support = parse_support(csv, "focal").unwrap();
+        assert_eq!(support.end.as_deref(), Some("2025-05-29"));
+        assert_eq!(support.esm_end.as_deref(), Some("2030-04-23"));
+        assert!(support.ended);
+        let support = parse_support(csv, "mantic").unwrap();
+        assert_eq!(support.esm_end, None);
+        assert!(parse_support(csv, "unknown").is_none());
+    }
+
+    #[test]
+    fn test_find_latest_kernel() {
+        let installed = [
+            "6.8.0-45-generic",
+            "6.8.0-47-generic",
+            "6.8.0-50-lowlatency",
+        ]
+        .map(str::to_owned);
+        assert_eq!(
+            find_latest_kernel("6.8.0-45-generic", &installed),
+            "6.8.0-47-generic"
+        );
+        assert_eq!(
+            find_latest_kernel("6.8.0-10-generic", &installed[..1]),
+            "6.8.0-45-generic"
+        );
+        assert_eq!(
+            find_latest_kernel("6.8.0-50-lowlatency", &installed),
+            "6.8.0-50-lowlatency"
+        );
+    }
+}
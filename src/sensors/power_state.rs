@@ -0,0 +1,73 @@
+use crate::main_loop::StopReason;
+use crate::sensor::{Sensor, SensorDiscovery, SensorDiscoveryInit};
+use anyhow::Error;
+use serde::Serialize;
+use std::sync::Mutex;
+
+const ID: &str = "power_state";
+
+pub struct PowerStateSensor {
+    topic: Box<str>,
+    state: Mutex<PowerState>,
+}
+
+impl PowerStateSensor {
+    pub fn new(topic_base: &str) -> Self {
+        let topic = format!("{topic_base}/{ID}").into_boxed_str();
+        PowerStateSensor {
+            topic,
+            state: Mutex::new(PowerState::Running),
+        }
+    }
+
+    pub fn set(&self, state: PowerState) {
+        *self.state.lock().unwrap() = state;
+    }
+}
+
+impl Sensor for PowerStateSensor {
+    type Payload = PowerState;
+
+    // Retain the state so that it survives the device going offline.
+    const RETAIN: bool = true;
+
+    fn topic(&self) -> &str {
+        self.topic.as_ref()
+    }
+
+    fn discovery_data(&self) -> Vec<SensorDiscovery<'_>> {
+        vec![
+            SensorDiscovery::new(SensorDiscoveryInit {
+                id: ID,
+                title: "Power state",
+                icon: "mdi:power",
+                value_template: "{{ value_json }}",
+            })
+            .with_always_available(true),
+        ]
+    }
+
+    async fn get_status(&self) -> Result<Self::Payload, Error> {
+        Ok(*self.state.lock().unwrap())
+    }
+}
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerState {
+    Running,
+    Suspending,
+    ShuttingDown,
+    /// The daemon is stopped while the system keeps running.
+    Stopped,
+}
+
+impl From<StopReason> for PowerState {
+    fn from(reason: StopReason) -> Self {
+        match reason {
+            StopReason::Shutdown => PowerState::Stopped,
+            StopReason::Sleep => PowerState::Suspending,
+            StopReason::PowerOff => PowerState::ShuttingDown,
+        }
+    }
+}
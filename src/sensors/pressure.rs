@@ -0,0 +1,252 @@
+use crate::config::Pressure as PressureConfig;
+use crate::ha::values::{EntityCategory, StateClass};
+use crate::sensor::{Sensor, SensorDiscovery, SensorDiscoveryInit};
+use anyhow::{Context, Error, ensure};
+use log::{info, warn};
+use serde::Serialize;
+use std::fs::{self, OpenOptions};
+use std::io::Write as _;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::Interest;
+use tokio::io::unix::AsyncFd;
+use tokio::sync::Notify;
+
+const ID: &str = "pressure";
+
+const PRESSURE_DIR: &str = "/proc/pressure";
+const RESOURCES: [(&str, &str); 3] = [("cpu", "CPU"), ("memory", "Memory"), ("io", "IO")];
+/// Window of the memory pressure trigger.
+///
+/// Unprivileged triggers need a window of a multiple of 2 seconds.
+const TRIGGER_WINDOW: Duration = Duration::from_secs(2);
+
+pub struct PressureSensor {
+    topic: Box<str>,
+    /// Notified when memory pressure spikes, if a trigger is configured.
+    spiked: Option<Arc<Notify>>,
+}
+
+impl PressureSensor {
+    /// Create the sensor, or `None` if pressure stall information is not available.
+    pub fn new(topic_base: &str, config: &PressureConfig) -> Result<Option<Self>, Error> {
+        if fs::read_to_string(Path::new(PRESSURE_DIR).join("cpu")).is_err() {
+            info!("Pressure stall information is not available");
+            return Ok(None);
+        }
+        let topic = format!("{topic_base}/{ID}").into_boxed_str();
+        let spiked = match config.memory_trigger_in_ms {
+            Some(stall) => {
+                let stall = Duration::from_millis(stall.into());
+                ensure!(
+                    !stall.is_zero() && stall < TRIGGER_WINDOW,
+                    "Memory trigger must be between 0 and {}ms",
+                    TRIGGER_WINDOW.as_millis(),
+                );
+                let spiked = Arc::new(Notify::new());
+                match watch_memory_pressure(stall, spiked.clone()) {
+                    Ok(()) => Some(spiked),
+                    Err(e) => {
+                        warn!("Failed to set up memory pressure trigger: {e:#}");
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
+        Ok(Some(PressureSensor { topic, spiked }))
+    }
+}
+
+impl Sensor for PressureSensor {
+    type Payload = Payload;
+
+    fn topic(&self) -> &str {
+        self.topic.as_ref()
+    }
+
+    fn discovery_data(&self) -> Vec<SensorDiscovery<'_>> {
+        let base_discovery = SensorDiscovery::new(SensorDiscoveryInit {
+            id: "",
+            title: "",
+            icon: "mdi:gauge",
+            value_template: "",
+        })
+        .with_entity_category(EntityCategory::Diagnostic)
+        .with_state_class(StateClass::Measurement)
+        .with_unit_of_measurement("%")
+        .with_suggested_display_precision(2);
+        let mut data = Vec::new();
+        for (resource, resource_title) in RESOURCES {
+            // Full CPU stall is undefined for the whole system.
+            let kinds = match resource {
+                "cpu" => &["some"][..],
+                _ => &["some", "full"][..],
+            };
+            for kind in kinds {
+                for (avg, avg_title) in [("avg10", "10s"), ("avg60", "1m"), ("avg300", "5m")] {
+                    data.push(SensorDiscovery {
+                        id: format!("{ID}_{resource}_{kind}_{avg}").into(),
+                        title: format!("{resource_title} pressure ({kind}, {avg_title})").into(),
+                        value_template: format!("{{{{ value_json.{resource}.{kind}.{avg} }}}}")
+                            .into(),
+                        ..base_discovery
+                    });
+                }
+            }
+        }
+        data
+    }
+
+    async fn get_status(&self) -> Result<Self::Payload, Error> {
+        let [cpu, memory, io] = RESOURCES.map(|(resource, _)| {
+            let path = Path::new(PRESSURE_DIR).join(resource);
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            parse_pressure(&content).with_context(|| format!("Failed to parse {resource} pressure"))
+        });
+        let mut cpu = cpu?;
+        cpu.full = None;
+        Ok(Payload {
+            cpu,
+            memory: memory?,
+            io: io?,
+        })
+    }
+
+    fn trigger(&self) -> Option<&Notify> {
+        self.spiked.as_deref()
+    }
+}
+
+#[derive(Serialize)]
+pub struct Payload {
+    cpu: Stall,
+    memory: Stall,
+    io: Stall,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct Stall {
+    /// Share of time some tasks are stalled.
+    some: Averages,
+    /// Share of time all non-idle tasks are stalled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    full: Option<Averages>,
+}
+
+/// Percentages averaged over 10 seconds, 1 minute and 5 minutes.
+#[derive(Debug, PartialEq, Serialize)]
+struct Averages {
+    avg10: f32,
+    avg60: f32,
+    avg300: f32,
+}
+
+/// Parse a pressure file, e.g. `/proc/pressure/memory`.
+fn parse_pressure(s: &str) -> Result<Stall, Error> {
+    let mut some = None;
+    let mut full = None;
+    for line in s.lines() {
+        let Some((kind, fields)) = line.split_once(' ') else {
+            continue;
+        };
+        let averages = parse_averages(fields).with_context(|| format!("For {kind} line"))?;
+        match kind {
+            "some" => some = Some(averages),
+            "full" => full = Some(averages),
+            _ => {}
+        }
+    }
+    Ok(Stall {
+        some: some.context("Expected some line")?,
+        full,
+    })
+}
+
+/// Parse fields like `avg10=0.00 avg60=0.00 avg300=0.00 total=0`.
+fn parse_averages(s: &str) -> Result<Averages, Error> {
+    let mut avg10 = None;
+    let mut avg60 = None;
+    let mut avg300 = None;
+    for field in s.split_ascii_whitespace() {
+        let (key, value) = field.split_once('=').context("Unexpected field format")?;
+        let slot = match key {
+            "avg10" => &mut avg10,
+            "avg60" => &mut avg60,
+            "avg300" => &mut avg300,
+            _ => continue,
+        };
+        *slot = Some(value.parse().with_context(|| format!("Invalid {key}"))?);
+    }
+    Ok(Averages {
+        avg10: avg10.context("Expected avg10")?,
+        avg60: avg60.context("Expected avg60")?,
+        avg300: avg300.context("Expected avg300")?,
+    })
+}
+
+/// Notify whenever some tasks stall on memory for the given time within the trigger window.
+fn watch_memory_pressure(stall: Duration, notify: Arc<Notify>) -> Result<(), Error> {
+    let path = Path::new(PRESSURE_DIR).join("memory");
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    // The kernel expects the trigger to be null-terminated.
+    let trigger = format!(
+        "some {} {}\0",
+        stall.as_micros(),
+        TRIGGER_WINDOW.as_micros()
+    );
+    file.write_all(trigger.as_bytes())
+        .context("Failed to write trigger")?;
+    let fd = AsyncFd::with_interest(file, Interest::PRIORITY)
+        .context("Failed to watch pressure file")?;
+    tokio::spawn(async move {
+        loop {
+            match fd.ready(Interest::PRIORITY).await {
+                Ok(mut guard) => {
+                    guard.clear_ready();
+                    notify.notify_one();
+                }
+                Err(e) => {
+                    warn!("Failed to wait for memory pressure: {e}");
+                    break;
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Averages, Stall, parse_pressure};
+
+    #[test]
+    fn test_parse_pressure() {
+        let memory = parse_pressure(include_str!("assets/pressure_memory_1")).unwrap();
+        assert_eq!(
+            memory,
+            Stall {
+                some: Averages {
+                    avg10: 1.53,
+                    avg60: 0.87,
+                    avg300: 0.26,
+                },
+                full: Some(Averages {
+                    avg10: 0.41,
+                    avg60: 0.22,
+                    avg300: 0.07,
+                }),
+            }
+        );
+        // Kernels before 5.13 report no full line for CPU.
+        let cpu = parse_pressure(include_str!("assets/pressure_cpu_1")).unwrap();
+        assert_eq!(cpu.some.avg10, 12.40);
+        assert_eq!(cpu.full, None);
+    }
+}
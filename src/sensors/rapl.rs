@@ -0,0 +1,309 @@
+use super::sampler::{Sample, Sampler};
+use crate::ha::values::{DeviceClass, StateClass};
+use crate::sensor::{Sensor, SensorDiscovery, SensorDiscoveryInit};
+use anyhow::{Context, Error};
+use log::{info, warn};
+use serde::Serialize;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const ID: &str = "rapl";
+
+const POWERCAP_DIR: &str = "/sys/class/powercap";
+const MICROJOULES_PER_KWH: f64 = 3.6e12;
+/// Maximum power of a zone deemed plausible, in watts,
+/// to tell counters reset, e.g. across suspend on some platforms, from wrapped around.
+const MAX_ZONE_POWER: f64 = 1000.;
+
+/// Power consumption of CPU packages from RAPL energy counters.
+pub struct RaplSensor {
+    topic: Box<str>,
+    has_core: bool,
+    has_dram: bool,
+    sampler: Sampler<Payload>,
+}
+
+impl RaplSensor {
+    /// Create the sensor, or `None` if RAPL energy counters are not available.
+    pub fn new(topic_base: &str) -> Result<Option<Self>, Error> {
+        let zones = list_zones()?;
+        if !zones.iter().any(|zone| zone.domain == Domain::Package) {
+            info!("RAPL energy counters are not available");
+            return Ok(None);
+        }
+        let first = match read_energy(&zones) {
+            Ok(energy) => energy,
+            Err(e) if is_permission_denied(&e) => {
+                warn!("RAPL energy counters are not readable, see README for granting access");
+                return Ok(None);
+            }
+            Err(e) => return Err(e),
+        };
+        let topic = format!("{topic_base}/{ID}").into_boxed_str();
+        let has_domain = |domain| zones.iter().any(|zone| zone.domain == domain);
+        let has_core = has_domain(Domain::Core);
+        let has_dram = has_domain(Domain::Dram);
+        let sample = RaplSample {
+            zones,
+            has_core,
+            has_dram,
+            energy: 0.,
+        };
+        Ok(Some(RaplSensor {
+            topic,
+            has_core,
+            has_dram,
+            sampler: Sampler::start(sample, first),
+        }))
+    }
+
+    pub fn resample(&self) {
+        self.sampler.resample();
+    }
+}
+
+/// Average power over the sample, and energy accumulated over all samples.
+struct RaplSample {
+    zones: Vec<Zone>,
+    has_core: bool,
+    has_dram: bool,
+    /// Energy consumed by packages since the daemon started, in kWh.
+    energy: f64,
+}
+
+impl Sample for RaplSample {
+    /// Energy counters in microjoules, in the same order as zones.
+    type Observation = Vec<u64>;
+    type Payload = Payload;
+
+    fn observe(&self) -> Result<Vec<u64>, Error> {
+        read_energy(&self.zones)
+    }
+
+    fn calculate(&mut self, start: &Vec<u64>, end: &Vec<u64>, duration: Duration) -> Payload {
+        let max_delta = (MAX_ZONE_POWER * 1e6 * duration.as_secs_f64()) as u64;
+        let consumed = |domain| {
+            Iterator::zip(start.iter(), end.iter())
+                .zip(self.zones.iter())
+                .filter(|(_, zone)| zone.domain == domain)
+                .map(|((start, end), zone)| {
+                    let delta = energy_delta(*start, *end, zone.max_energy_range, max_delta);
+                    if delta.is_none() {
+                        warn!("Discarding change of RAPL energy counter, as it was reset");
+                    }
+                    delta.unwrap_or(0)
+                })
+                .sum::<u64>()
+        };
+        let power = |energy: u64| {
+            let watts = energy as f64 / 1e6 / duration.as_secs_f64();
+            ((watts * 100.).round() / 100.) as f32
+        };
+        let package = consumed(Domain::Package);
+        self.energy += package as f64 / MICROJOULES_PER_KWH;
+        Payload {
+            package: power(package),
+            core: self.has_core.then(|| power(consumed(Domain::Core))),
+            dram: self.has_dram.then(|| power(consumed(Domain::Dram))),
+            energy: self.energy,
+        }
+    }
+}
+
+impl Sensor for RaplSensor {
+    type Payload = Payload;
+
+    fn topic(&self) -> &str {
+        self.topic.as_ref()
+    }
+
+    fn discovery_data(&self) -> Vec<SensorDiscovery<'_>> {
+        let base_discovery = SensorDiscovery::new(SensorDiscoveryInit {
+            id: "",
+            title: "",
+            icon: "mdi:lightning-bolt",
+            value_template: "",
+        })
+        .with_device_class(DeviceClass::Power)
+        .with_state_class(StateClass::Measurement)
+        .with_unit_of_measurement("W")
+        .with_suggested_display_precision(1);
+        let mut data = vec![SensorDiscovery {
+            id: "cpu_package_power".into(),
+            title: "CPU package power".into(),
+            value_template: "{{ value_json.package }}".into(),
+            ..base_discovery
+        }];
+        if self.has_core {
+            data.push(SensorDiscovery {
+                id: "cpu_core_power".into(),
+                title: "CPU core power".into(),
+                value_template: "{{ value_json.core }}".into(),
+                ..base_discovery
+            });
+        }
+        if self.has_dram {
+            data.push(SensorDiscovery {
+                id: "dram_power".into(),
+                title: "DRAM power".into(),
+                value_template: "{{ value_json.dram }}".into(),
+                ..base_discovery
+            });
+        }
+        data.push(
+            SensorDiscovery::new(SensorDiscoveryInit {
+                id: "cpu_package_energy",
+                title: "CPU package energy",
+                icon: "mdi:lightning-bolt",
+                value_template: "{{ value_json.energy }}",
+            })
+            .with_device_class(DeviceClass::Energy)
+            .with_state_class(StateClass::TotalIncreasing)
+            .with_unit_of_measurement("kWh")
+            .with_suggested_display_precision(3),
+        );
+        data
+    }
+
+    async fn get_status(&self) -> Result<Self::Payload, Error> {
+        self.sampler.next().await
+    }
+}
+
+#[derive(Clone, Default, Serialize)]
+pub struct Payload {
+    /// Power of all packages in watts.
+    package: f32,
+    /// Power of cores of all packages in watts.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    core: Option<f32>,
+    /// Power of DRAM of all packages in watts.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dram: Option<f32>,
+    /// Energy consumed by all packages since the daemon started, in kWh.
+    energy: f64,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Domain {
+    Package,
+    Core,
+    Dram,
+}
+
+impl Domain {
+    /// Get the domain from the name of a zone, e.g. `package-0`.
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "core" => Some(Domain::Core),
+            "dram" => Some(Domain::Dram),
+            _ if name.starts_with("package-") => Some(Domain::Package),
+            // Others, e.g. psys, overlap with packages.
+            _ => None,
+        }
+    }
+}
+
+/// A RAPL zone, e.g. `/sys/class/powercap/intel-rapl:0`.
+struct Zone {
+    domain: Domain,
+    energy_file: PathBuf,
+    /// Value in microjoules at which the energy counter wraps around.
+    max_energy_range: u64,
+}
+
+/// List zones of known domains.
+///
+/// Zones of packages are named like `intel-rapl:0`, and their subzones like `intel-rapl:0:0`,
+/// which are used by AMD CPUs as well.
+fn list_zones() -> Result<Vec<Zone>, Error> {
+    let entries = match fs::read_dir(POWERCAP_DIR) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).context("Failed to list powercap zones"),
+    };
+    let mut zones = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let is_rapl = entry
+            .file_name()
+            .to_str()
+            .is_some_and(|name| name.starts_with("intel-rapl:"));
+        if !is_rapl {
+            continue;
+        }
+        let path = entry.path();
+        let name = read_file(&path.join("name"))?;
+        let Some(domain) = Domain::from_name(name.trim()) else {
+            continue;
+        };
+        let max_energy_range = read_file(&path.join("max_energy_range_uj"))?
+            .trim()
+            .parse()
+            .context("Failed to parse max energy range")?;
+        zones.push(Zone {
+            domain,
+            energy_file: path.join("energy_uj"),
+            max_energy_range,
+        });
+    }
+    Ok(zones)
+}
+
+/// Read energy counters in microjoules, in the same order as zones.
+fn read_energy(zones: &[Zone]) -> Result<Vec<u64>, Error> {
+    zones
+        .iter()
+        .map(|zone| {
+            read_file(&zone.energy_file)?
+                .trim()
+                .parse()
+                .context("Failed to parse energy")
+        })
+        .collect()
+}
+
+/// Energy consumed between two readings of a counter, which may have wrapped around,
+/// or `None` if it's more than `max_delta`, as the counter must have been reset instead.
+fn energy_delta(start: u64, end: u64, max_energy_range: u64, max_delta: u64) -> Option<u64> {
+    let delta = match end.checked_sub(start) {
+        Some(delta) => delta,
+        // The counter wraps from the max energy range to 0.
+        None => max_energy_range.saturating_sub(start) + end + 1,
+    };
+    (delta <= max_delta).then_some(delta)
+}
+
+fn read_file(path: &Path) -> Result<String, Error> {
+    fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))
+}
+
+fn is_permission_denied(error: &Error) -> bool {
+    error
+        .root_cause()
+        .downcast_ref::<std::io::Error>()
+        .is_some_and(|e| e.kind() == ErrorKind::PermissionDenied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::energy_delta;
+
+    #[test]
+    fn test_energy_delta() {
+        const MAX: u64 = 262_143_328_850;
+        const MAX_DELTA: u64 = 60_000_000_000;
+        assert_eq!(energy_delta(1_000, 5_000, MAX, MAX_DELTA), Some(4_000));
+        // The counter wrapped around.
+        assert_eq!(
+            energy_delta(262_143_000_000, 1_000_000, MAX, MAX_DELTA),
+            Some(1_328_851)
+        );
+        // A reading beyond the max energy range.
+        assert_eq!(energy_delta(MAX + 10, 5, MAX, MAX_DELTA), Some(6));
+        // The counter was reset, e.g. across suspend.
+        assert_eq!(energy_delta(100_000_000_000, 1_000, MAX, MAX_DELTA), None);
+    }
+}
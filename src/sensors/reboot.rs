@@ -1,24 +1,28 @@
+use crate::commands::AptJobs;
 use crate::sensor::{Sensor, SensorDiscovery, SensorDiscoveryInit};
 use anyhow::{Context, Error};
 use futures_util::TryStreamExt;
 use log::warn;
 use serde::Serialize;
 use std::io;
+use std::sync::Arc;
 use tokio::fs;
 use tokio::fs::File;
 use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::Notify;
 use tokio_stream::wrappers::LinesStream;
 
 const ID: &str = "reboot";
 
 pub struct RebootSensor {
     topic: Box<str>,
+    apt_jobs: Arc<AptJobs>,
 }
 
 impl RebootSensor {
-    pub fn new(topic_base: &str) -> RebootSensor {
+    pub fn new(topic_base: &str, apt_jobs: Arc<AptJobs>) -> RebootSensor {
         let topic = format!("{topic_base}/{ID}").into_boxed_str();
-        RebootSensor { topic }
+        RebootSensor { topic, apt_jobs }
     }
 }
 
@@ -57,6 +61,11 @@ impl Sensor for RebootSensor {
         let attrs = Attrs { packages };
         Ok(Payload { state, attrs })
     }
+
+    fn trigger(&self) -> Option<&Notify> {
+        // Upgrades may require a reboot.
+        Some(&self.apt_jobs.finished)
+    }
 }
 
 async fn read_packages() -> Result<Vec<String>, Error> {
@@ -0,0 +1,69 @@
+use crate::ha::values::StateClass;
+use crate::sensor::{Sensor, SensorDiscovery, SensorDiscoveryInit};
+use crate::utils::needrestart;
+use anyhow::{Context, Error};
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::Notify;
+use tokio::task;
+
+const ID: &str = "services_restart";
+
+pub struct RestartSensor {
+    topic: Box<str>,
+    restarted: Arc<Notify>,
+}
+
+impl RestartSensor {
+    pub fn new(topic_base: &str, restarted: Arc<Notify>) -> Self {
+        let topic = format!("{topic_base}/{ID}").into_boxed_str();
+        RestartSensor { topic, restarted }
+    }
+}
+
+impl Sensor for RestartSensor {
+    type Payload = Payload;
+
+    fn topic(&self) -> &str {
+        self.topic.as_ref()
+    }
+
+    fn discovery_data(&self) -> Vec<SensorDiscovery<'_>> {
+        vec![
+            SensorDiscovery::new(SensorDiscoveryInit {
+                id: ID,
+                title: "Services needing restart",
+                icon: "mdi:restart-alert",
+                value_template: "{{ value_json.state }}",
+            })
+            .with_state_class(StateClass::Measurement)
+            .with_attributes("{{ value_json.attrs | tojson }}"),
+        ]
+    }
+
+    async fn get_status(&self) -> Result<Self::Payload, Error> {
+        let units = task::spawn_blocking(needrestart::find_units_needing_restart)
+            .await?
+            .context("Failed to find services needing restart")?;
+        let units = units.into_iter().collect::<Vec<_>>();
+        Ok(Payload {
+            state: units.len(),
+            attrs: Attrs { units },
+        })
+    }
+
+    fn trigger(&self) -> Option<&Notify> {
+        Some(&self.restarted)
+    }
+}
+
+#[derive(Serialize)]
+pub struct Payload {
+    state: usize,
+    attrs: Attrs,
+}
+
+#[derive(Serialize)]
+struct Attrs {
+    units: Vec<String>,
+}
@@ -0,0 +1,57 @@
+use crate::ha::values::{DeviceClass, EntityCategory};
+use crate::sensor::{Sensor, SensorDiscovery, SensorDiscoveryInit};
+use crate::utils::serialize::serialize_timestamp_opt;
+use anyhow::Error;
+use serde::Serialize;
+use std::sync::Mutex;
+use time::OffsetDateTime;
+
+const ID: &str = "resume";
+
+pub struct ResumeSensor {
+    topic: Box<str>,
+    last_resumed: Mutex<Option<OffsetDateTime>>,
+}
+
+impl ResumeSensor {
+    pub fn new(topic_base: &str) -> Self {
+        let topic = format!("{topic_base}/{ID}").into_boxed_str();
+        ResumeSensor {
+            topic,
+            last_resumed: Mutex::new(None),
+        }
+    }
+
+    pub fn record_resume(&self) {
+        *self.last_resumed.lock().unwrap() = Some(OffsetDateTime::now_utc());
+    }
+}
+
+impl Sensor for ResumeSensor {
+    type Payload = Payload;
+
+    fn topic(&self) -> &str {
+        self.topic.as_ref()
+    }
+
+    fn discovery_data(&self) -> Vec<SensorDiscovery<'_>> {
+        vec![
+            SensorDiscovery::new(SensorDiscoveryInit {
+                id: ID,
+                title: "Last resumed",
+                icon: "mdi:sleep-off",
+                value_template: "{{ value_json }}",
+            })
+            .with_entity_category(EntityCategory::Diagnostic)
+            .with_device_class(DeviceClass::Timestamp),
+        ]
+    }
+
+    async fn get_status(&self) -> Result<Self::Payload, Error> {
+        Ok(Payload(*self.last_resumed.lock().unwrap()))
+    }
+}
+
+#[derive(Serialize)]
+#[serde(transparent)]
+pub struct Payload(#[serde(serialize_with = "serialize_timestamp_opt")] Option<OffsetDateTime>);
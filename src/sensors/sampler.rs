@@ -0,0 +1,121 @@
+use anyhow::{Context, Error};
+use log::error;
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::select;
+use tokio::sync::{Mutex, Notify, watch};
+use tokio::time::sleep;
+
+/// Duration of the regular sample.
+const SAMPLE_DURATION: Duration = Duration::from_secs(60);
+/// Duration of the sample taken when a fresh value is requested.
+const RESAMPLE_DURATION: Duration = Duration::from_secs(1);
+
+/// Values calculated from the change between two observations, e.g. of cumulative counters.
+pub trait Sample: Send + 'static {
+    type Observation: Send;
+    type Payload: Clone + Default + Send + Sync + 'static;
+
+    fn observe(&self) -> Result<Self::Observation, Error>;
+
+    /// Calculate the payload from two observations taken `duration` apart.
+    fn calculate(
+        &mut self,
+        start: &Self::Observation,
+        end: &Self::Observation,
+        duration: Duration,
+    ) -> Self::Payload;
+}
+
+/// Samples values in the background, keeping the latest payload for the sensor to publish.
+pub struct Sampler<P> {
+    rx: Mutex<watch::Receiver<P>>,
+    resample: Arc<Notify>,
+}
+
+impl<P: Clone + Default + Send + Sync + 'static> Sampler<P> {
+    /// Start sampling from the observation just taken.
+    pub fn start<S: Sample<Payload = P>>(sample: S, first: S::Observation) -> Self {
+        let (tx, rx) = watch::channel(P::default());
+        let resample = Arc::new(Notify::new());
+        let resample_requested = resample.clone();
+        tokio::spawn(async move {
+            let Err(e) = run(sample, first, tx, &resample_requested).await;
+            error!("Stopped sampling: {e:#}");
+        });
+        Sampler {
+            rx: Mutex::new(rx),
+            resample,
+        }
+    }
+
+    /// Discard the pending value and take a short sample right away.
+    pub fn resample(&self) {
+        if let Ok(mut rx) = self.rx.try_lock() {
+            rx.mark_unchanged();
+        }
+        self.resample.notify_one();
+    }
+
+    /// Wait for the next value sampled.
+    pub async fn next(&self) -> Result<P, Error> {
+        let mut rx = self.rx.try_lock().context("Failed to acquire receiver")?;
+        rx.changed().await.context("Failed to wait for receiver")?;
+        let payload = rx.borrow_and_update().clone();
+        Ok(payload)
+    }
+}
+
+async fn run<S: Sample>(
+    mut sample: S,
+    first: S::Observation,
+    tx: watch::Sender<S::Payload>,
+    resample_requested: &Notify,
+) -> Result<Infallible, Error> {
+    let mut last = Observed {
+        timestamp: Instant::now(),
+        observation: first,
+    };
+    loop {
+        select! {
+            () = sleep(SAMPLE_DURATION) => {}
+            () = resample_requested.notified() => {
+                // Still account for the time so far, which matters for cumulative values.
+                let obs = Observed::new(&sample)?;
+                obs.calculate_since(&last, &mut sample);
+                last = obs;
+                sleep(RESAMPLE_DURATION).await;
+            }
+        }
+        let obs = Observed::new(&sample)?;
+        let payload = obs.calculate_since(&last, &mut sample);
+        tx.send(payload).context("Failed to update payload")?;
+        last = obs;
+    }
+}
+
+struct Observed<O> {
+    timestamp: Instant,
+    observation: O,
+}
+
+impl<O> Observed<O> {
+    fn new<S: Sample<Observation = O>>(sample: &S) -> Result<Self, Error> {
+        let timestamp = Instant::now();
+        let observation = sample.observe()?;
+        Ok(Self {
+            timestamp,
+            observation,
+        })
+    }
+
+    fn calculate_since<S: Sample<Observation = O>>(
+        &self,
+        last: &Self,
+        sample: &mut S,
+    ) -> S::Payload {
+        let duration = self.timestamp - last.timestamp;
+        sample.calculate(&last.observation, &self.observation, duration)
+    }
+}
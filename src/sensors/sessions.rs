@@ -0,0 +1,188 @@
+use crate::ha::values::StateClass;
+use crate::sensor::{Sensor, SensorDiscovery, SensorDiscoveryInit};
+use crate::utils::logind;
+use anyhow::{Context, Error};
+use log::debug;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use tokio::sync::Notify;
+use zbus::Connection;
+
+const ID: &str = "sessions";
+
+pub struct SessionsSensor {
+    topic: Box<str>,
+    connection: Connection,
+    changed: Arc<Notify>,
+}
+
+impl SessionsSensor {
+    pub fn new(topic_base: &str, connection: &Connection) -> Self {
+        let topic = format!("{topic_base}/{ID}").into_boxed_str();
+        let changed = Arc::new(Notify::new());
+        logind::notify_on_signals(
+            connection,
+            &["SessionNew", "SessionRemoved"],
+            changed.clone(),
+        );
+        SessionsSensor {
+            topic,
+            connection: connection.clone(),
+            changed,
+        }
+    }
+
+    async fn read_session(&self, session: logind::Session) -> Result<Option<SessionInfo>, Error> {
+        let proxy = session.proxy(&self.connection).await?;
+        let class: String = proxy.get_property("Class").await.context("Class")?;
+        let state: String = proxy.get_property("State").await.context("State")?;
+        // Skip greeters and sessions that are left behind after logging out.
+        if class != "user" || state == "closing" {
+            return Ok(None);
+        }
+        let remote: bool = proxy.get_property("Remote").await.context("Remote")?;
+        let remote_host: String = proxy
+            .get_property("RemoteHost")
+            .await
+            .context("RemoteHost")?;
+        let r#type: String = proxy.get_property("Type").await.context("Type")?;
+        Ok(Some(SessionInfo {
+            id: session.id,
+            user: session.user,
+            r#type,
+            state,
+            seat: Some(session.seat).filter(|seat| !seat.is_empty()),
+            remote,
+            remote_host: Some(remote_host).filter(|host| !host.is_empty()),
+        }))
+    }
+}
+
+impl Sensor for SessionsSensor {
+    type Payload = Payload;
+
+    fn topic(&self) -> &str {
+        self.topic.as_ref()
+    }
+
+    fn discovery_data(&self) -> Vec<SensorDiscovery<'_>> {
+        vec![
+            SensorDiscovery::new(SensorDiscoveryInit {
+                id: ID,
+                title: "Sessions",
+                icon: "mdi:account-multiple",
+                value_template: "{{ value_json.state }}",
+            })
+            .with_state_class(StateClass::Measurement)
+            .with_attributes("{{ value_json.attrs | tojson }}"),
+            SensorDiscovery::new(SensorDiscoveryInit {
+                id: "sessions_remote",
+                title: "Remote sessions",
+                icon: "mdi:remote-desktop",
+                value_template: "{{ value_json.remote.state }}",
+            })
+            .with_state_class(StateClass::Measurement)
+            .with_attributes("{{ value_json.remote.attrs | tojson }}"),
+            SensorDiscovery::new(SensorDiscoveryInit {
+                id: "sessions_local",
+                title: "Local sessions",
+                icon: "mdi:monitor-account",
+                value_template: "{{ value_json.local.state }}",
+            })
+            .with_state_class(StateClass::Measurement)
+            .with_attributes("{{ value_json.local.attrs | tojson }}"),
+        ]
+    }
+
+    async fn get_status(&self) -> Result<Self::Payload, Error> {
+        let mut sessions = Vec::new();
+        for session in logind::list_sessions(&self.connection).await? {
+            let id = session.id.clone();
+            match self.read_session(session).await {
+                Ok(info) => sessions.extend(info),
+                // The session may have gone away since listing.
+                Err(e) => debug!("Skipping session {id}: {e:#}"),
+            }
+        }
+        let (remote, local) = sessions
+            .iter()
+            .partition::<Vec<_>, _>(|session| session.remote);
+        Ok(Payload {
+            state: sessions.len(),
+            remote: SessionCount::new(&remote),
+            local: SessionCount::new(&local),
+            attrs: Attrs {
+                users: collect_users(&sessions),
+                sessions,
+            },
+        })
+    }
+
+    fn trigger(&self) -> Option<&Notify> {
+        Some(&self.changed)
+    }
+}
+
+#[derive(Serialize)]
+pub struct Payload {
+    /// Number of sessions of users.
+    state: usize,
+    attrs: Attrs,
+    remote: SessionCount,
+    local: SessionCount,
+}
+
+#[derive(Serialize)]
+struct Attrs {
+    users: BTreeSet<String>,
+    sessions: Vec<SessionInfo>,
+}
+
+#[derive(Serialize)]
+struct SessionInfo {
+    id: String,
+    user: String,
+    /// Type of the session, e.g. `tty`, `x11` or `wayland`.
+    r#type: String,
+    /// State of the session, `online` or `active`.
+    state: String,
+    seat: Option<String>,
+    remote: bool,
+    remote_host: Option<String>,
+}
+
+#[derive(Serialize)]
+struct SessionCount {
+    state: usize,
+    attrs: SessionCountAttrs,
+}
+
+#[derive(Serialize)]
+struct SessionCountAttrs {
+    users: BTreeSet<String>,
+    /// Hosts the sessions come from, only known for remote ones.
+    remote_hosts: BTreeSet<String>,
+}
+
+impl SessionCount {
+    fn new(sessions: &[&SessionInfo]) -> Self {
+        SessionCount {
+            state: sessions.len(),
+            attrs: SessionCountAttrs {
+                users: collect_users(sessions.iter().copied()),
+                remote_hosts: sessions
+                    .iter()
+                    .filter_map(|session| session.remote_host.clone())
+                    .collect(),
+            },
+        }
+    }
+}
+
+fn collect_users<'a>(sessions: impl IntoIterator<Item = &'a SessionInfo>) -> BTreeSet<String> {
+    sessions
+        .into_iter()
+        .map(|session| session.user.clone())
+        .collect()
+}
@@ -0,0 +1,156 @@
+use crate::ha::values::{DeviceClass, EntityCategory, StateClass};
+use crate::sensor::{Sensor, SensorDiscovery, SensorDiscoveryInit};
+use crate::utils::snapd;
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, Notify};
+use tokio::time::Instant;
+
+const ID: &str = "snap";
+/// How often to query the Snap Store for pending refreshes.
+const STORE_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+pub struct SnapSensor {
+    topic: Box<str>,
+    refreshed: Arc<Notify>,
+    last_check: Mutex<Option<StoreCheck>>,
+}
+
+impl SnapSensor {
+    pub fn new(topic_base: &str, refreshed: Arc<Notify>) -> Self {
+        let topic = format!("{topic_base}/{ID}").into_boxed_str();
+        SnapSensor {
+            topic,
+            refreshed,
+            last_check: Mutex::new(None),
+        }
+    }
+}
+
+impl Sensor for SnapSensor {
+    type Payload = Payload;
+
+    fn topic(&self) -> &str {
+        self.topic.as_ref()
+    }
+
+    fn discovery_data(&self) -> Vec<SensorDiscovery<'_>> {
+        vec![
+            SensorDiscovery::new(SensorDiscoveryInit {
+                id: ID,
+                title: "Snap pending refreshes",
+                icon: "mdi:package-down",
+                value_template: "{{ value_json.state }}",
+            })
+            .with_state_class(StateClass::Measurement)
+            .with_attributes("{{ value_json.attrs | tojson }}"),
+            SensorDiscovery::new(SensorDiscoveryInit {
+                id: "snap_next_refresh",
+                title: "Snap next refresh",
+                icon: "mdi:calendar-clock",
+                value_template: "{{ value_json.next_refresh }}",
+            })
+            .with_entity_category(EntityCategory::Diagnostic)
+            .with_device_class(DeviceClass::Timestamp),
+        ]
+    }
+
+    async fn get_status(&self) -> Result<Self::Payload, Error> {
+        let installed = snapd::get::<Vec<Snap>>("/v2/snaps", None)
+            .await?
+            .unwrap_or_default();
+        let system_info = snapd::get::<SystemInfo>("/v2/system-info", None).await?;
+
+        // Checking pending refreshes queries the Snap Store,
+        // so reuse the last result unless it's stale or installed snaps have changed since.
+        let revisions = installed
+            .iter()
+            .map(|snap| (snap.name.clone(), snap.revision.clone()))
+            .collect::<BTreeMap<_, _>>();
+        let mut last_check = self.last_check.lock().await;
+        let fresh_check = last_check.as_ref().filter(|check| {
+            check.checked_at.elapsed() < STORE_CHECK_INTERVAL && check.revisions == revisions
+        });
+        let packages = match fresh_check {
+            Some(check) => check.packages.clone(),
+            None => {
+                // snapd reports no pending refresh as an error.
+                let refreshes =
+                    snapd::get::<Vec<Snap>>("/v2/find?select=refresh", Some("snap-not-found"))
+                        .await?
+                        .unwrap_or_default();
+                let packages = refreshes
+                    .into_iter()
+                    .map(|snap| snap.name)
+                    .collect::<Vec<_>>();
+                *last_check = Some(StoreCheck {
+                    checked_at: Instant::now(),
+                    revisions,
+                    packages: packages.clone(),
+                });
+                packages
+            }
+        };
+
+        let held = installed
+            .into_iter()
+            .filter(|snap| snap.hold.is_some())
+            .map(|snap| snap.name)
+            .collect();
+        let attrs = Attrs { packages, held };
+        Ok(Payload {
+            state: attrs.packages.len(),
+            attrs,
+            next_refresh: system_info.and_then(|info| info.refresh.next),
+        })
+    }
+
+    fn trigger(&self) -> Option<&Notify> {
+        Some(&self.refreshed)
+    }
+}
+
+#[derive(Serialize)]
+pub struct Payload {
+    state: usize,
+    attrs: Attrs,
+    /// Time of the next scheduled refresh, as reported by snapd.
+    next_refresh: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Attrs {
+    packages: Vec<String>,
+    /// Snaps held from refreshing.
+    held: Vec<String>,
+}
+
+/// Pending refreshes found in the Snap Store.
+struct StoreCheck {
+    checked_at: Instant,
+    /// Revisions of installed snaps at the time.
+    revisions: BTreeMap<String, String>,
+    packages: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Snap {
+    name: String,
+    #[serde(default)]
+    revision: String,
+    /// Time until which the snap is held from refreshing.
+    hold: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SystemInfo {
+    refresh: RefreshInfo,
+}
+
+#[derive(Deserialize)]
+struct RefreshInfo {
+    next: Option<String>,
+}
@@ -0,0 +1,108 @@
+use super::memory::{VmStat, parse_vm_stat};
+use super::sampler::{Sample, Sampler};
+use crate::ha::values::{DeviceClass, EntityCategory, StateClass};
+use crate::sensor::{Sensor, SensorDiscovery, SensorDiscoveryInit};
+use anyhow::{Context, Error};
+use nix::unistd::{SysconfVar, sysconf};
+use serde::Serialize;
+use std::fs;
+use std::time::Duration;
+
+const ID: &str = "swap";
+
+/// Rates of swapping in and out.
+pub struct SwapSensor {
+    topic: Box<str>,
+    sampler: Sampler<Payload>,
+}
+
+impl SwapSensor {
+    pub fn new(topic_base: &str) -> Result<Self, Error> {
+        let topic = format!("{topic_base}/{ID}").into_boxed_str();
+        let page_size = sysconf(SysconfVar::PAGE_SIZE)
+            .context("Failed to read page size")?
+            .context("Expected page size to be available")? as u64;
+        let first = read_vm_stat()?;
+        Ok(SwapSensor {
+            topic,
+            sampler: Sampler::start(SwapSample { page_size }, first),
+        })
+    }
+
+    pub fn resample(&self) {
+        self.sampler.resample();
+    }
+}
+
+/// Average rates of swapping over the sample.
+struct SwapSample {
+    page_size: u64,
+}
+
+impl Sample for SwapSample {
+    type Observation = VmStat;
+    type Payload = Payload;
+
+    fn observe(&self) -> Result<VmStat, Error> {
+        read_vm_stat()
+    }
+
+    fn calculate(&mut self, start: &VmStat, end: &VmStat, duration: Duration) -> Payload {
+        let calc = |f: fn(&VmStat) -> u64| {
+            let pages = f(end).saturating_sub(f(start));
+            ((pages * self.page_size) as f32 / duration.as_secs_f32()).round()
+        };
+        Payload {
+            bytes_in: calc(|s| s.pswpin),
+            bytes_out: calc(|s| s.pswpout),
+        }
+    }
+}
+
+impl Sensor for SwapSensor {
+    type Payload = Payload;
+
+    fn topic(&self) -> &str {
+        self.topic.as_ref()
+    }
+
+    fn discovery_data(&self) -> Vec<SensorDiscovery<'_>> {
+        vec![
+            SensorDiscovery::new(SensorDiscoveryInit {
+                id: "swap_in",
+                title: "Swap in",
+                icon: "mdi:swap-vertical",
+                value_template: "{{ value_json.bytes_in }}",
+            })
+            .with_entity_category(EntityCategory::Diagnostic)
+            .with_device_class(DeviceClass::DataRate)
+            .with_state_class(StateClass::Measurement)
+            .with_unit_of_measurement("B/s"),
+            SensorDiscovery::new(SensorDiscoveryInit {
+                id: "swap_out",
+                title: "Swap out",
+                icon: "mdi:swap-vertical",
+                value_template: "{{ value_json.bytes_out }}",
+            })
+            .with_entity_category(EntityCategory::Diagnostic)
+            .with_device_class(DeviceClass::DataRate)
+            .with_state_class(StateClass::Measurement)
+            .with_unit_of_measurement("B/s"),
+        ]
+    }
+
+    async fn get_status(&self) -> Result<Self::Payload, Error> {
+        self.sampler.next().await
+    }
+}
+
+#[derive(Clone, Default, Serialize)]
+pub struct Payload {
+    bytes_in: f32,
+    bytes_out: f32,
+}
+
+fn read_vm_stat() -> Result<VmStat, Error> {
+    let vm_stat = fs::read_to_string("/proc/vmstat").context("Failed to read vmstat")?;
+    parse_vm_stat(&vm_stat).context("Failed to parse vmstat")
+}
@@ -0,0 +1,43 @@
+use crate::sensor::{Sensor, SensorDiscovery};
+use crate::switch::SwitchState;
+use anyhow::Error;
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::Notify;
+
+/// Publishes the state of a switch, which is discovered along with its command.
+pub struct SwitchSensor {
+    state: Arc<SwitchState>,
+}
+
+impl SwitchSensor {
+    pub fn new(state: Arc<SwitchState>) -> Self {
+        Self { state }
+    }
+}
+
+impl Sensor for SwitchSensor {
+    type Payload = Payload;
+
+    fn topic(&self) -> &str {
+        &self.state.topic
+    }
+
+    fn discovery_data(&self) -> Vec<SensorDiscovery<'_>> {
+        vec![]
+    }
+
+    async fn get_status(&self) -> Result<Self::Payload, Error> {
+        let on = self.state.switch.read_state().await?;
+        Ok(Payload { on })
+    }
+
+    fn trigger(&self) -> Option<&Notify> {
+        Some(&self.state.changed)
+    }
+}
+
+#[derive(Serialize)]
+pub struct Payload {
+    on: bool,
+}
@@ -0,0 +1,278 @@
+use crate::ha::values::{DeviceClass, EntityCategory};
+use crate::sensor::{Sensor, SensorDiscovery, SensorDiscoveryInit};
+use crate::utils::serialize::serialize_timestamp_opt;
+use anyhow::{Context, Error};
+use log::warn;
+use regex::Regex;
+use serde::Serialize;
+use std::io;
+use std::path::Path;
+use std::sync::LazyLock;
+use std::time::Duration;
+use time::{Date, Month, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
+use tokio::fs;
+use tz::TimeZone;
+
+const ID: &str = "unattended_upgrades";
+
+const LOG_PATH: &str = "/var/log/unattended-upgrades/unattended-upgrades.log";
+const PERIODIC_DIR: &str = "/var/lib/apt/periodic";
+
+/// Age after which package lists are considered stale.
+const STALE_LISTS_AGE: Duration = Duration::from_secs(2 * 24 * 60 * 60);
+
+pub struct UnattendedUpgradesSensor {
+    topic: Box<str>,
+}
+
+impl UnattendedUpgradesSensor {
+    pub fn new(topic_base: &str) -> Self {
+        let topic = format!("{topic_base}/{ID}").into_boxed_str();
+        UnattendedUpgradesSensor { topic }
+    }
+}
+
+impl Sensor for UnattendedUpgradesSensor {
+    type Payload = Payload;
+
+    fn topic(&self) -> &str {
+        self.topic.as_ref()
+    }
+
+    fn discovery_data(&self) -> Vec<SensorDiscovery<'_>> {
+        vec![
+            SensorDiscovery::new(SensorDiscoveryInit {
+                id: "unattended_upgrades_last_run",
+                title: "Unattended upgrades last run",
+                icon: "mdi:robot",
+                value_template: "{{ value_json.last_run.started_at if value_json.last_run else None }}",
+            })
+            .with_entity_category(EntityCategory::Diagnostic)
+            .with_device_class(DeviceClass::Timestamp)
+            .with_attributes("{{ value_json.last_run | tojson }}"),
+            SensorDiscovery::new(SensorDiscoveryInit {
+                id: "unattended_upgrades_last_result",
+                title: "Unattended upgrades last result",
+                icon: "mdi:robot",
+                value_template: "{{ value_json.last_run.result if value_json.last_run else None }}",
+            })
+            .with_entity_category(EntityCategory::Diagnostic),
+            SensorDiscovery::new(SensorDiscoveryInit {
+                id: "apt_lists_updated",
+                title: "APT package lists updated",
+                icon: "mdi:database-refresh",
+                value_template: "{{ value_json.lists_updated }}",
+            })
+            .with_entity_category(EntityCategory::Diagnostic)
+            .with_device_class(DeviceClass::Timestamp)
+            .with_attributes("{{ value_json.stamps | tojson }}"),
+            SensorDiscovery::new(SensorDiscoveryInit {
+                id: "apt_lists_stale",
+                title: "APT package lists stale",
+                icon: "mdi:database-alert",
+                value_template: "{{ 'ON' if value_json.lists_stale else 'OFF' }}",
+            })
+            .with_binary(true)
+            .with_device_class(DeviceClass::Problem),
+        ]
+    }
+
+    async fn get_status(&self) -> Result<Self::Payload, Error> {
+        let last_run = read_last_run().await.unwrap_or_else(|e| {
+            warn!("Failed to read unattended-upgrades log: {e}");
+            None
+        });
+        let stamps = Stamps {
+            update: read_stamp("update-stamp").await?,
+            update_success: read_stamp("update-success-stamp").await?,
+            upgrade: read_stamp("upgrade-stamp").await?,
+            unattended_upgrades: read_stamp("unattended-upgrades-stamp").await?,
+        };
+        let lists_updated = stamps.update_success;
+        let lists_stale =
+            lists_updated.is_none_or(|time| OffsetDateTime::now_utc() - time > STALE_LISTS_AGE);
+        Ok(Payload {
+            last_run,
+            lists_updated,
+            lists_stale,
+            stamps,
+        })
+    }
+}
+
+#[derive(Serialize)]
+pub struct Payload {
+    last_run: Option<Run>,
+    /// Time of the last successful update of package lists.
+    #[serde(serialize_with = "serialize_timestamp_opt")]
+    lists_updated: Option<OffsetDateTime>,
+    lists_stale: bool,
+    stamps: Stamps,
+}
+
+/// Modification time of stamps in `/var/lib/apt/periodic`.
+#[derive(Serialize)]
+struct Stamps {
+    #[serde(serialize_with = "serialize_timestamp_opt")]
+    update: Option<OffsetDateTime>,
+    #[serde(serialize_with = "serialize_timestamp_opt")]
+    update_success: Option<OffsetDateTime>,
+    #[serde(serialize_with = "serialize_timestamp_opt")]
+    upgrade: Option<OffsetDateTime>,
+    #[serde(serialize_with = "serialize_timestamp_opt")]
+    unattended_upgrades: Option<OffsetDateTime>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct Run {
+    #[serde(serialize_with = "serialize_timestamp_opt")]
+    started_at: Option<OffsetDateTime>,
+    result: RunResult,
+    packages: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum RunResult {
+    Succeeded,
+    NothingToDo,
+    Failed,
+    /// The run hasn't finished, or its result is not recognized.
+    Unknown,
+}
+
+async fn read_stamp(name: &str) -> Result<Option<OffsetDateTime>, Error> {
+    match fs::metadata(Path::new(PERIODIC_DIR).join(name)).await {
+        Ok(metadata) => Ok(Some(metadata.modified()?.into())),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {name}")),
+    }
+}
+
+async fn read_last_run() -> Result<Option<Run>, Error> {
+    let log = match fs::read_to_string(LOG_PATH).await {
+        Ok(log) => log,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).context("Failed to read log"),
+    };
+    // The log is written in local time.
+    let time_zone = TimeZone::local().unwrap_or_else(|e| {
+        warn!("Failed to read local time zone: {e}");
+        TimeZone::utc()
+    });
+    Ok(parse_last_run(&log, &time_zone))
+}
+
+static REGEX_LOG_LINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?<y>\d{4})-(?<mon>\d{2})-(?<d>\d{2}) (?<h>\d{2}):(?<min>\d{2}):(?<s>\d{2}),(?<ms>\d{3}) (?<level>[A-Z]+) (?<message>.*)$",
+    )
+    .unwrap()
+});
+
+/// Parse the last run from the log of unattended-upgrades.
+fn parse_last_run(log: &str, time_zone: &TimeZone) -> Option<Run> {
+    let mut run = None;
+    for line in log.lines() {
+        let Some(captures) = REGEX_LOG_LINE.captures(line) else {
+            continue;
+        };
+        let message = &captures["message"];
+        if message == "Starting unattended upgrades script" {
+            run = Some(Run {
+                started_at: parse_time(&captures, time_zone),
+                result: RunResult::Unknown,
+                packages: Vec::new(),
+            });
+            continue;
+        }
+        let Some(run) = &mut run else {
+            continue;
+        };
+        if &captures["level"] == "ERROR" {
+            run.result = RunResult::Failed;
+        } else if let Some(packages) = message.strip_prefix("Packages that will be upgraded: ") {
+            run.packages = packages.split_whitespace().map(str::to_owned).collect();
+        } else if message == "All upgrades installed" && run.result != RunResult::Failed {
+            run.result = RunResult::Succeeded;
+        } else if message.starts_with("No packages found that can be upgraded unattended") {
+            run.result = RunResult::NothingToDo;
+        }
+    }
+    run
+}
+
+fn parse_time(captures: &regex::Captures<'_>, time_zone: &TimeZone) -> Option<OffsetDateTime> {
+    let field = |name: &str| captures[name].parse::<u16>().ok();
+    let month = Month::try_from(field("mon")? as u8).ok()?;
+    let date = Date::from_calendar_date(field("y")? as i32, month, field("d")? as u8).ok()?;
+    let time = Time::from_hms_milli(
+        field("h")? as u8,
+        field("min")? as u8,
+        field("s")? as u8,
+        field("ms")?,
+    )
+    .ok()?;
+    assume_local(PrimitiveDateTime::new(date, time), time_zone)
+}
+
+/// Interpret a local time with the offset of the time zone in effect at that time.
+fn assume_local(local: PrimitiveDateTime, time_zone: &TimeZone) -> Option<OffsetDateTime> {
+    let offset_at = |unix_time| {
+        let ut_offset = time_zone.find_local_time_type(unix_time).ok()?.ut_offset();
+        UtcOffset::from_whole_seconds(ut_offset).ok()
+    };
+    // Reading the local time as UTC is off by the offset, which may be on the other side
+    // of a transition, so look the offset up again at the time adjusted by the first guess.
+    let as_utc = local.assume_utc().unix_timestamp();
+    let guess = offset_at(as_utc)?;
+    let offset = offset_at(as_utc - i64::from(guess.whole_seconds()))?;
+    Some(local.assume_offset(offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Run, RunResult, assume_local, parse_last_run};
+    use time::{Date, Month, PrimitiveDateTime, Time, UtcOffset};
+    use tz::TimeZone;
+
+    #[test]
+    fn test_parse_last_run() {
+        let log = include_str!("assets/unattended_upgrades_1");
+        let time_zone = TimeZone::fixed(2 * 60 * 60).unwrap();
+        let started_at = PrimitiveDateTime::new(
+            Date::from_calendar_date(2024, Month::October, 2).unwrap(),
+            Time::from_hms_milli(6, 41, 12, 870).unwrap(),
+        )
+        .assume_offset(UtcOffset::from_hms(2, 0, 0).unwrap());
+        assert_eq!(
+            parse_last_run(log, &time_zone),
+            Some(Run {
+                started_at: Some(started_at),
+                result: RunResult::Succeeded,
+                packages: vec!["libc-bin".into(), "libc6".into(), "openssl".into()],
+            })
+        );
+        assert_eq!(parse_last_run("", &time_zone), None);
+    }
+
+    #[test]
+    fn test_assume_local() {
+        let time_zone = TimeZone::from_posix_tz("CET-1CEST,M3.5.0,M10.5.0/3").unwrap();
+        let offset_on = |month, day, hour| {
+            let local = PrimitiveDateTime::new(
+                Date::from_calendar_date(2024, month, day).unwrap(),
+                Time::from_hms(hour, 30, 0).unwrap(),
+            );
+            assume_local(local, &time_zone)
+                .unwrap()
+                .offset()
+                .whole_hours()
+        };
+        assert_eq!(offset_on(Month::January, 15, 12), 1);
+        assert_eq!(offset_on(Month::July, 15, 12), 2);
+        // Right after summer time starts at 02:00 on March 31, and ends at 03:00 on October 27.
+        assert_eq!(offset_on(Month::March, 31, 3), 2);
+        assert_eq!(offset_on(Month::October, 27, 3), 1);
+    }
+}
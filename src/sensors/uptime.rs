@@ -0,0 +1,213 @@
+use crate::ha::values::{DeviceClass, EntityCategory};
+use crate::sensor::{Sensor, SensorDiscovery, SensorDiscoveryInit};
+use crate::utils::parser::parse_next_field;
+use crate::utils::serialize::{serialize_timestamp, serialize_timestamp_opt};
+use anyhow::{Context, Error};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use time::OffsetDateTime;
+use tokio::process::Command;
+use tokio::sync::OnceCell;
+
+const ID: &str = "uptime";
+
+/// Message ID logged by systemd-logind when the system starts shutting down.
+const SHUTDOWN_MESSAGE_ID: &str = "98268866d1d54a499c4e98921d93bc40";
+
+pub struct UptimeSensor {
+    topic: Box<str>,
+    /// How the previous boot ended, which doesn't change while running.
+    previous_shutdown: OnceCell<PreviousShutdown>,
+}
+
+impl UptimeSensor {
+    pub fn new(topic_base: &str) -> Self {
+        let topic = format!("{topic_base}/{ID}").into_boxed_str();
+        UptimeSensor {
+            topic,
+            previous_shutdown: OnceCell::new(),
+        }
+    }
+}
+
+impl Sensor for UptimeSensor {
+    type Payload = Payload;
+
+    fn topic(&self) -> &str {
+        self.topic.as_ref()
+    }
+
+    fn discovery_data(&self) -> Vec<SensorDiscovery<'_>> {
+        vec![
+            SensorDiscovery::new(SensorDiscoveryInit {
+                id: "boot_time",
+                title: "Boot time",
+                icon: "mdi:restart",
+                value_template: "{{ value_json.boot_time }}",
+            })
+            .with_entity_category(EntityCategory::Diagnostic)
+            .with_device_class(DeviceClass::Timestamp),
+            SensorDiscovery::new(SensorDiscoveryInit {
+                id: ID,
+                title: "Uptime",
+                icon: "mdi:timer-outline",
+                value_template: "{{ value_json.uptime }}",
+            })
+            .with_entity_category(EntityCategory::Diagnostic)
+            .with_device_class(DeviceClass::Duration)
+            .with_unit_of_measurement("s")
+            .with_suggested_display_precision(0),
+            SensorDiscovery::new(SensorDiscoveryInit {
+                id: "previous_shutdown",
+                title: "Previous shutdown",
+                icon: "mdi:power-settings",
+                value_template: "{{ value_json.previous_shutdown.reason }}",
+            })
+            .with_entity_category(EntityCategory::Diagnostic)
+            .with_attributes("{{ value_json.previous_shutdown | tojson }}"),
+        ]
+    }
+
+    async fn get_status(&self) -> Result<Self::Payload, Error> {
+        let stat = fs::read_to_string("/proc/stat").context("Failed to read stat file")?;
+        let boot_time = parse_boot_time(&stat)?;
+        let uptime = fs::read_to_string("/proc/uptime").context("Failed to read uptime file")?;
+        let uptime: f64 =
+            parse_next_field(&mut uptime.split_ascii_whitespace()).context("uptime")?;
+        let previous_shutdown = self
+            .previous_shutdown
+            .get_or_init(|| async {
+                read_previous_shutdown().await.unwrap_or_else(|e| {
+                    warn!("Failed to read previous shutdown: {e}");
+                    PreviousShutdown::default()
+                })
+            })
+            .await
+            .clone();
+        Ok(Payload {
+            boot_time,
+            uptime: uptime as u64,
+            previous_shutdown,
+        })
+    }
+}
+
+#[derive(Serialize)]
+pub struct Payload {
+    #[serde(serialize_with = "serialize_timestamp")]
+    boot_time: OffsetDateTime,
+    /// Seconds since boot.
+    uptime: u64,
+    previous_shutdown: PreviousShutdown,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+struct PreviousShutdown {
+    /// How the previous boot ended, e.g. `reboot` or `power-off` as logged by systemd-logind,
+    /// `unclean` if it ended without logging a shutdown, or `unknown` if it isn't in the journal.
+    reason: String,
+    /// When the shutdown started, or when the last entry was logged for unclean ones.
+    #[serde(serialize_with = "serialize_timestamp_opt")]
+    at: Option<OffsetDateTime>,
+    message: Option<String>,
+}
+
+impl Default for PreviousShutdown {
+    fn default() -> Self {
+        PreviousShutdown {
+            reason: "unknown".to_owned(),
+            at: None,
+            message: None,
+        }
+    }
+}
+
+fn parse_boot_time(stat: &str) -> Result<OffsetDateTime, Error> {
+    let btime = stat
+        .lines()
+        .find_map(|line| line.strip_prefix("btime "))
+        .context("Boot time not found in stat file")?;
+    let btime = btime.trim().parse().context("Invalid boot time")?;
+    OffsetDateTime::from_unix_timestamp(btime).context("Boot time out of range")
+}
+
+async fn read_previous_shutdown() -> Result<PreviousShutdown, Error> {
+    // Boots missing from the journal, e.g. when it isn't persistent, fail to query.
+    let Some(last_entry) = query_previous_boot(&[]).await? else {
+        return Ok(PreviousShutdown::default());
+    };
+    let match_shutdown = format!("MESSAGE_ID={SHUTDOWN_MESSAGE_ID}");
+    // The previous boot is known to exist now, so nothing found means no shutdown was logged.
+    let previous_shutdown = match query_previous_boot(&[&match_shutdown]).await? {
+        Some(entry) => PreviousShutdown {
+            at: entry.timestamp(),
+            reason: entry.shutdown.unwrap_or_else(|| "shutdown".to_owned()),
+            message: entry.message,
+        },
+        None => PreviousShutdown {
+            reason: "unclean".to_owned(),
+            at: last_entry.timestamp(),
+            message: None,
+        },
+    };
+    Ok(previous_shutdown)
+}
+
+/// Get the last journal entry of the previous boot matching the given matches,
+/// or `None` if nothing matches or the previous boot is not in the journal.
+async fn query_previous_boot(matches: &[&str]) -> Result<Option<JournalEntry>, Error> {
+    let output = Command::new("journalctl")
+        .args(["--boot=-1", "--lines=1", "--output=json", "--quiet"])
+        .args(matches)
+        .output()
+        .await
+        .context("Failed to execute journalctl")?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+    let output = String::from_utf8_lossy(&output.stdout);
+    output.lines().next().map(parse_journal_entry).transpose()
+}
+
+#[derive(Debug, Deserialize)]
+struct JournalEntry {
+    /// Microseconds since the epoch.
+    #[serde(rename = "__REALTIME_TIMESTAMP")]
+    realtime_timestamp: Option<String>,
+    #[serde(rename = "MESSAGE")]
+    message: Option<String>,
+    #[serde(rename = "SHUTDOWN")]
+    shutdown: Option<String>,
+}
+
+impl JournalEntry {
+    fn timestamp(&self) -> Option<OffsetDateTime> {
+        let micros = self.realtime_timestamp.as_ref()?.parse::<i128>().ok()?;
+        OffsetDateTime::from_unix_timestamp_nanos(micros * 1000).ok()
+    }
+}
+
+fn parse_journal_entry(line: &str) -> Result<JournalEntry, Error> {
+    serde_json::from_str(line).context("Failed to parse journal entry")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_boot_time, parse_journal_entry};
+
+    #[test]
+    fn test_parse_boot_time() {
+        let stat = include_str!("assets/stat_1");
+        let boot_time = parse_boot_time(stat).unwrap();
+        assert_eq!(boot_time.unix_timestamp(), 1726912263);
+    }
+
+    #[test]
+    fn test_parse_journal_entry() {
+        let entry = parse_journal_entry(include_str!("assets/journal_shutdown_1")).unwrap();
+        assert_eq!(entry.shutdown.as_deref(), Some("reboot"));
+        assert_eq!(entry.message.as_deref(), Some("System is rebooting."));
+        assert_eq!(entry.timestamp().unwrap().unix_timestamp(), 1729105523);
+    }
+}
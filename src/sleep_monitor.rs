@@ -1,3 +1,4 @@
+use crate::utils::logind::{self, LOGIN1_MANAGER};
 use anyhow::{Context, Result};
 use futures_util::Stream;
 use futures_util::stream::StreamExt;
@@ -5,17 +6,17 @@ use log::warn;
 use std::os::fd::OwnedFd;
 use zbus::message::Type as MessageType;
 use zbus::names::{InterfaceName, MemberName};
-use zbus::{Connection, MatchRule, MessageStream, Proxy, zvariant};
+use zbus::{Connection, MatchRule, MessageStream, zvariant};
 
-const LOGIN1: &str = "org.freedesktop.login1";
-const LOGIN1_PATH: &str = "/org/freedesktop/login1";
-const LOGIN1_MANAGER: &str = "org.freedesktop.login1.Manager";
 const PREPARE_FOR_SLEEP: &str = "PrepareForSleep";
+const PREPARE_FOR_SHUTDOWN: &str = "PrepareForShutdown";
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SleepEvent {
     PreparingSleep,
     WakingUp,
+    PreparingShutdown,
+    ShutdownCancelled,
 }
 
 pub struct SleepMonitor {
@@ -30,8 +31,18 @@ impl SleepMonitor {
         Ok(Self { connection })
     }
 
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
     pub async fn take_inhibitor_lock(&self) -> Result<InhibitorLock> {
-        InhibitorLock::new(&self.connection).await
+        InhibitorLock::new(
+            &self.connection,
+            "sleep:shutdown",
+            "Need to report unavailability before sleep or shutdown",
+            "delay",
+        )
+        .await
     }
 
     pub async fn start_monitoring(&self) -> Result<impl Stream<Item = Result<SleepEvent>>> {
@@ -39,12 +50,10 @@ impl SleepMonitor {
             .msg_type(MessageType::Signal)
             .interface(LOGIN1_MANAGER)
             .unwrap()
-            .member(PREPARE_FOR_SLEEP)
-            .unwrap()
             .build();
         let stream = MessageStream::for_match_rule(match_rule, &self.connection, None)
             .await
-            .context("Failed to create message stream for PrepareForSleep signal")?;
+            .context("Failed to create message stream for login1 manager signals")?;
 
         let stream = stream.filter_map(async |msg| {
             let msg = match msg {
@@ -52,21 +61,23 @@ impl SleepMonitor {
                 Err(e) => return Some(Err(e).context("Failed to receive message")),
             };
             let header = msg.header();
-            if header.interface().map(InterfaceName::as_str) != Some(LOGIN1_MANAGER)
-                || header.member().map(MemberName::as_str) != Some(PREPARE_FOR_SLEEP)
-            {
+            if header.interface().map(InterfaceName::as_str) != Some(LOGIN1_MANAGER) {
                 return None;
             }
+            let member = header.member().map(MemberName::as_str)?;
+            let (start, end) = match member {
+                PREPARE_FOR_SLEEP => (SleepEvent::PreparingSleep, SleepEvent::WakingUp),
+                PREPARE_FOR_SHUTDOWN => {
+                    (SleepEvent::PreparingShutdown, SleepEvent::ShutdownCancelled)
+                }
+                _ => return None,
+            };
             let body = msg.body();
-            let Ok(preparing_for_sleep) = body.deserialize::<bool>() else {
-                warn!("Failed to deserialize PrepareForSleep signal body");
+            let Ok(preparing) = body.deserialize::<bool>() else {
+                warn!("Failed to deserialize {member} signal body");
                 return None;
             };
-            Some(Ok(if preparing_for_sleep {
-                SleepEvent::PreparingSleep
-            } else {
-                SleepEvent::WakingUp
-            }))
+            Some(Ok(if preparing { start } else { end }))
         });
         Ok(stream)
     }
@@ -78,20 +89,10 @@ pub struct InhibitorLock {
 }
 
 impl InhibitorLock {
-    async fn new(connection: &Connection) -> Result<Self> {
-        let proxy = Proxy::new(connection, LOGIN1, LOGIN1_PATH, LOGIN1_MANAGER)
-            .await
-            .context("Failed to create login1 manager proxy")?;
-        let reply = proxy
-            .call_method(
-                "Inhibit",
-                &(
-                    "sleep",
-                    "ubuntu-mqtt2ha-reporter",
-                    "Need to report unavailability before sleep",
-                    "delay",
-                ),
-            )
+    pub async fn new(connection: &Connection, what: &str, why: &str, mode: &str) -> Result<Self> {
+        let reply = logind::manager_proxy(connection)
+            .await?
+            .call_method("Inhibit", &(what, "ubuntu-mqtt2ha-reporter", why, mode))
             .await
             .context("Failed to call Inhibit method")?;
         let fd = reply
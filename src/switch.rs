@@ -0,0 +1,22 @@
+use crate::command::CommandDiscovery;
+use anyhow::Error;
+use async_trait::async_trait;
+use tokio::sync::Notify;
+
+/// A control with an on/off state, exposed as a switch entity.
+#[async_trait]
+pub trait Switch: Send + Sync + 'static {
+    fn id(&self) -> &str;
+    fn discovery_data(&self) -> CommandDiscovery<'_>;
+    async fn read_state(&self) -> Result<bool, Error>;
+    async fn apply(&self, on: bool) -> Result<(), Error>;
+}
+
+/// A switch shared between the command applying its state and the sensor publishing it.
+pub struct SwitchState {
+    pub switch: Box<dyn Switch>,
+    /// Topic the state is published to.
+    pub topic: Box<str>,
+    /// Notified when the state has been applied, so that it gets published right away.
+    pub changed: Notify,
+}
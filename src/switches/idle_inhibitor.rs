@@ -0,0 +1,68 @@
+use crate::command::{CommandDiscovery, CommandDiscoveryInit};
+use crate::sleep_monitor::InhibitorLock;
+use crate::switch::Switch;
+use anyhow::Error;
+use async_trait::async_trait;
+use log::info;
+use tokio::sync::Mutex;
+use zbus::Connection;
+
+const ID: &str = "idle_inhibitor";
+
+/// Blocks the system from going idle, which keeps the screen from locking.
+pub struct IdleInhibitorSwitch {
+    connection: Connection,
+    lock: Mutex<Option<InhibitorLock>>,
+}
+
+impl IdleInhibitorSwitch {
+    pub fn new(connection: Connection) -> Self {
+        Self {
+            connection,
+            lock: Mutex::new(None),
+        }
+    }
+}
+
+#[async_trait]
+impl Switch for IdleInhibitorSwitch {
+    fn id(&self) -> &str {
+        ID
+    }
+
+    fn discovery_data(&self) -> CommandDiscovery<'_> {
+        CommandDiscovery::new(CommandDiscoveryInit {
+            id: ID,
+            name: "Inhibit screen lock",
+            icon: "mdi:monitor-lock",
+        })
+    }
+
+    async fn read_state(&self) -> Result<bool, Error> {
+        Ok(self.lock.lock().await.is_some())
+    }
+
+    async fn apply(&self, on: bool) -> Result<(), Error> {
+        let mut lock = self.lock.lock().await;
+        match (on, lock.is_some()) {
+            (true, false) => {
+                info!("Taking idle inhibitor lock");
+                *lock = Some(
+                    InhibitorLock::new(
+                        &self.connection,
+                        "idle",
+                        "Requested from Home Assistant",
+                        "block",
+                    )
+                    .await?,
+                );
+            }
+            (false, true) => {
+                info!("Releasing idle inhibitor lock");
+                *lock = None;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
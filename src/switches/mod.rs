@@ -0,0 +1,28 @@
+use self::idle_inhibitor::IdleInhibitorSwitch;
+use self::systemd_unit::SystemdUnitSwitch;
+use self::wifi::WifiSwitch;
+use crate::config::Config;
+use crate::switch::Switch;
+use zbus::Connection;
+
+mod idle_inhibitor;
+mod systemd_unit;
+mod wifi;
+
+pub fn create_switches(config: &Config, connection: &Connection) -> Vec<Box<dyn Switch>> {
+    let config = &config.switches;
+    let mut switches = Vec::<Box<dyn Switch>>::new();
+    if config.idle_inhibitor {
+        switches.push(Box::new(IdleInhibitorSwitch::new(connection.clone())));
+    }
+    if config.wifi {
+        switches.push(Box::new(WifiSwitch::new(connection.clone())));
+    }
+    switches.extend(
+        config
+            .systemd_units
+            .iter()
+            .map(|unit| Box::new(SystemdUnitSwitch::new(unit)) as Box<dyn Switch>),
+    );
+    switches
+}
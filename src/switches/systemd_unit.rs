@@ -0,0 +1,75 @@
+use crate::command::{CommandDiscovery, CommandDiscoveryInit};
+use crate::ha::values::EntityCategory;
+use crate::switch::Switch;
+use crate::utils::snake_case::make_snake_case;
+use anyhow::{Context, Error, anyhow, ensure};
+use async_trait::async_trait;
+use log::info;
+use tokio::process;
+
+/// Whether a systemd unit is enabled.
+pub struct SystemdUnitSwitch {
+    id: String,
+    name: String,
+    unit: String,
+}
+
+impl SystemdUnitSwitch {
+    pub fn new(unit: &str) -> Self {
+        Self {
+            id: format!("systemd_unit_{}", make_snake_case(unit)),
+            name: format!("Enable {unit}"),
+            unit: unit.to_owned(),
+        }
+    }
+}
+
+#[async_trait]
+impl Switch for SystemdUnitSwitch {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn discovery_data(&self) -> CommandDiscovery<'_> {
+        CommandDiscovery::new(CommandDiscoveryInit {
+            id: self.id.as_str(),
+            name: self.name.as_str(),
+            icon: "mdi:cog",
+        })
+        .with_entity_category(EntityCategory::Config)
+    }
+
+    async fn read_state(&self) -> Result<bool, Error> {
+        // is-enabled exits with failure for disabled units, so only look at its output.
+        let output = process::Command::new("systemctl")
+            .args(["is-enabled", "--", &self.unit])
+            .output()
+            .await
+            .context("Failed to execute systemctl is-enabled")?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let state = stdout.trim();
+        ensure!(
+            !state.is_empty(),
+            "Failed to get state of {}: {}",
+            self.unit,
+            String::from_utf8_lossy(&output.stderr).trim(),
+        );
+        Ok(matches!(state, "enabled" | "enabled-runtime"))
+    }
+
+    async fn apply(&self, on: bool) -> Result<(), Error> {
+        let action = if on { "enable" } else { "disable" };
+        info!("Executing systemctl {action} {}", self.unit);
+        let output = process::Command::new("sudo")
+            // The unit is after `--` so it can't be taken as an option.
+            .args(["-n", "/usr/bin/systemctl", action, "--", &self.unit])
+            .output()
+            .await
+            .with_context(|| format!("Failed to execute systemctl {action}"))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow!("systemctl {action} {} failed: {stderr}", self.unit));
+        }
+        Ok(())
+    }
+}
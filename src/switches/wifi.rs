@@ -0,0 +1,68 @@
+use crate::command::{CommandDiscovery, CommandDiscoveryInit};
+use crate::ha::values::EntityCategory;
+use crate::switch::Switch;
+use anyhow::{Context, Error};
+use async_trait::async_trait;
+use log::info;
+use zbus::{Connection, Proxy};
+
+const ID: &str = "wifi";
+
+const NETWORK_MANAGER: &str = "org.freedesktop.NetworkManager";
+const NETWORK_MANAGER_PATH: &str = "/org/freedesktop/NetworkManager";
+const WIRELESS_ENABLED: &str = "WirelessEnabled";
+
+/// Wi-Fi radio, controlled via NetworkManager.
+pub struct WifiSwitch {
+    connection: Connection,
+}
+
+impl WifiSwitch {
+    pub fn new(connection: Connection) -> Self {
+        Self { connection }
+    }
+
+    async fn proxy(&self) -> Result<Proxy<'_>, Error> {
+        Proxy::new(
+            &self.connection,
+            NETWORK_MANAGER,
+            NETWORK_MANAGER_PATH,
+            NETWORK_MANAGER,
+        )
+        .await
+        .context("Failed to create NetworkManager proxy")
+    }
+}
+
+#[async_trait]
+impl Switch for WifiSwitch {
+    fn id(&self) -> &str {
+        ID
+    }
+
+    fn discovery_data(&self) -> CommandDiscovery<'_> {
+        CommandDiscovery::new(CommandDiscoveryInit {
+            id: ID,
+            name: "Wi-Fi",
+            icon: "mdi:wifi",
+        })
+        .with_entity_category(EntityCategory::Config)
+    }
+
+    async fn read_state(&self) -> Result<bool, Error> {
+        self.proxy()
+            .await?
+            .get_property(WIRELESS_ENABLED)
+            .await
+            .context("Failed to get WirelessEnabled property")
+    }
+
+    async fn apply(&self, on: bool) -> Result<(), Error> {
+        info!("Setting Wi-Fi radio to {on}");
+        self.proxy()
+            .await?
+            .set_property(WIRELESS_ENABLED, on)
+            .await
+            .context("Failed to set WirelessEnabled property")
+    }
+}
@@ -0,0 +1,49 @@
+use log::warn;
+use sd_notify::NotifyState;
+use std::future::pending;
+use tokio::time::{Interval, MissedTickBehavior, interval};
+
+pub fn notify_ready() {
+    notify(&[NotifyState::Ready]);
+}
+
+pub fn notify_status(status: &str) {
+    notify(&[NotifyState::Status(status)]);
+}
+
+fn notify(state: &[NotifyState]) {
+    // This is a no-op when not running under systemd.
+    if let Err(e) = sd_notify::notify(state) {
+        warn!("Failed to notify systemd: {e}");
+    }
+}
+
+/// Pings the systemd watchdog, if it's enabled for the service.
+pub struct Watchdog {
+    interval: Option<Interval>,
+}
+
+impl Watchdog {
+    pub fn new() -> Self {
+        let interval = sd_notify::watchdog_enabled().map(|timeout| {
+            // Ping at half of the timeout, as recommended by sd_watchdog_enabled(3).
+            let mut interval = interval(timeout / 2);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            interval
+        });
+        Self { interval }
+    }
+
+    /// Wait until the next ping is due and send it.
+    ///
+    /// This never completes if the watchdog is disabled.
+    pub async fn ping(&mut self) {
+        match &mut self.interval {
+            Some(interval) => {
+                interval.tick().await;
+                notify(&[NotifyState::Watchdog]);
+            }
+            None => pending().await,
+        }
+    }
+}
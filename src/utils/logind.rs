@@ -0,0 +1,175 @@
+use anyhow::{Context, Error};
+use futures_util::StreamExt as _;
+use log::warn;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Notify;
+use zbus::message::Type as MessageType;
+use zbus::names::InterfaceName;
+use zbus::proxy::{Builder as ProxyBuilder, CacheProperties};
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue};
+use zbus::{Connection, MatchRule, Message, MessageStream, Proxy};
+
+const LOGIN1: &str = "org.freedesktop.login1";
+const LOGIN1_PATH: &str = "/org/freedesktop/login1";
+pub const LOGIN1_MANAGER: &str = "org.freedesktop.login1.Manager";
+const LOGIN1_SESSION: &str = "org.freedesktop.login1.Session";
+const LOGIN1_SEAT: &str = "org.freedesktop.login1.Seat";
+const MAIN_SEAT_PATH: &str = "/org/freedesktop/login1/seat/seat0";
+const PROPERTIES: &str = "org.freedesktop.DBus.Properties";
+
+pub async fn manager_proxy(connection: &Connection) -> Result<Proxy<'static>, Error> {
+    object_proxy(connection, LOGIN1_PATH, LOGIN1_MANAGER)
+        .await
+        .context("Failed to create login1 manager proxy")
+}
+
+/// Create a proxy to a logind object.
+///
+/// Properties are not cached, as objects are only looked at briefly.
+async fn object_proxy<P>(
+    connection: &Connection,
+    path: P,
+    interface: &'static str,
+) -> zbus::Result<Proxy<'static>>
+where
+    P: TryInto<ObjectPath<'static>>,
+    P::Error: Into<zbus::Error>,
+{
+    ProxyBuilder::new(connection)
+        .destination(LOGIN1)?
+        .path(path)?
+        .interface(interface)?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await
+}
+
+/// A session as listed by `ListSessions`.
+pub struct Session {
+    pub id: String,
+    pub user: String,
+    /// Seat of the session, empty for sessions without one, e.g. remote ones.
+    pub seat: String,
+    pub path: OwnedObjectPath,
+}
+
+impl Session {
+    pub async fn proxy(&self, connection: &Connection) -> Result<Proxy<'static>, Error> {
+        object_proxy(connection, self.path.clone(), LOGIN1_SESSION)
+            .await
+            .with_context(|| format!("Failed to create proxy for session {}", self.id))
+    }
+}
+
+pub async fn list_sessions(connection: &Connection) -> Result<Vec<Session>, Error> {
+    let reply = manager_proxy(connection)
+        .await?
+        .call_method("ListSessions", &())
+        .await
+        .context("Failed to call ListSessions method")?;
+    let sessions = reply
+        .body()
+        .deserialize::<Vec<(String, u32, String, String, OwnedObjectPath)>>()
+        .context("Failed to deserialize sessions")?;
+    Ok(sessions
+        .into_iter()
+        .map(|(id, _uid, user, seat, path)| Session {
+            id,
+            user,
+            seat,
+            path,
+        })
+        .collect())
+}
+
+/// Get a proxy to the session shown on the screen of the main seat, if any.
+pub async fn active_session_proxy(
+    connection: &Connection,
+) -> Result<Option<Proxy<'static>>, Error> {
+    let seat = object_proxy(connection, MAIN_SEAT_PATH, LOGIN1_SEAT)
+        .await
+        .context("Failed to create proxy for the main seat")?;
+    let (id, path): (String, OwnedObjectPath) = seat
+        .get_property("ActiveSession")
+        .await
+        .context("Failed to get ActiveSession property")?;
+    if id.is_empty() {
+        return Ok(None);
+    }
+    let proxy = object_proxy(connection, path, LOGIN1_SESSION)
+        .await
+        .with_context(|| format!("Failed to create proxy for session {id}"))?;
+    Ok(Some(proxy))
+}
+
+/// Notify whenever the manager emits any of the given signals, in the background.
+pub fn notify_on_signals(
+    connection: &Connection,
+    members: &'static [&'static str],
+    notify: Arc<Notify>,
+) {
+    let match_rule = MatchRule::builder()
+        .msg_type(MessageType::Signal)
+        .interface(LOGIN1_MANAGER)
+        .unwrap()
+        .build();
+    notify_on_messages(connection, match_rule, notify, move |msg| {
+        let header = msg.header();
+        header.interface().map(InterfaceName::as_str) == Some(LOGIN1_MANAGER)
+            && header
+                .member()
+                .is_some_and(|member| members.contains(&member.as_str()))
+    });
+}
+
+/// Notify whenever any of the given properties changes on any logind object, in the background.
+pub fn notify_on_property_changes(
+    connection: &Connection,
+    properties: &'static [&'static str],
+    notify: Arc<Notify>,
+) {
+    let match_rule = MatchRule::builder()
+        .msg_type(MessageType::Signal)
+        .interface(PROPERTIES)
+        .unwrap()
+        .member("PropertiesChanged")
+        .unwrap()
+        .path_namespace(LOGIN1_PATH)
+        .unwrap()
+        .build();
+    notify_on_messages(connection, match_rule, notify, move |msg| {
+        let body = msg.body();
+        let Ok((_, changed, invalidated)) =
+            body.deserialize::<(&str, HashMap<&str, OwnedValue>, Vec<&str>)>()
+        else {
+            return false;
+        };
+        properties
+            .iter()
+            .any(|property| changed.contains_key(property) || invalidated.contains(property))
+    });
+}
+
+fn notify_on_messages(
+    connection: &Connection,
+    match_rule: MatchRule<'static>,
+    notify: Arc<Notify>,
+    filter: impl Fn(&Message) -> bool + Send + 'static,
+) {
+    let connection = connection.clone();
+    tokio::spawn(async move {
+        let mut stream = match MessageStream::for_match_rule(match_rule, &connection, None).await {
+            Ok(stream) => stream,
+            Err(e) => {
+                warn!("Failed to watch login1 signals: {e}");
+                return;
+            }
+        };
+        while let Some(msg) = stream.next().await {
+            if msg.is_ok_and(|msg| filter(&msg)) {
+                notify.notify_one();
+            }
+        }
+    });
+}
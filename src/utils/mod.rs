@@ -1,3 +1,6 @@
+pub mod logind;
+pub mod needrestart;
 pub mod parser;
 pub mod serialize;
 pub mod snake_case;
+pub mod snapd;
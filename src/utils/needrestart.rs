@@ -0,0 +1,102 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::{fs, io};
+
+/// Prefixes of paths whose deleted files indicate an upgraded binary or library.
+const CODE_PREFIXES: &[&str] = &["/usr/", "/lib", "/bin/", "/sbin/", "/opt/"];
+
+/// Find system units with processes still using deleted binaries or libraries,
+/// which usually means they need a restart after an upgrade.
+///
+/// Processes whose memory maps can't be read are skipped.
+pub fn find_units_needing_restart() -> Result<BTreeSet<String>, io::Error> {
+    let mut units = BTreeSet::new();
+    for entry in fs::read_dir("/proc")? {
+        let path = entry?.path();
+        let is_pid = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.bytes().all(|b| b.is_ascii_digit()));
+        if !is_pid {
+            continue;
+        }
+        if let Some(unit) = check_process(&path) {
+            units.insert(unit);
+        }
+    }
+    Ok(units)
+}
+
+/// Get the system service the daemon itself runs in, if any.
+pub fn own_unit() -> Option<String> {
+    let cgroup = fs::read_to_string("/proc/self/cgroup").ok()?;
+    parse_system_unit(&cgroup).map(str::to_owned)
+}
+
+fn check_process(path: &Path) -> Option<String> {
+    // Processes may exit or be inaccessible at any point, so just ignore errors.
+    let maps = fs::read_to_string(path.join("maps")).ok()?;
+    if !has_deleted_code(&maps) {
+        return None;
+    }
+    let cgroup = fs::read_to_string(path.join("cgroup")).ok()?;
+    parse_system_unit(&cgroup).map(str::to_owned)
+}
+
+/// Whether any mapped binary or library in `/proc/<pid>/maps` has been deleted.
+fn has_deleted_code(maps: &str) -> bool {
+    maps.lines().any(|line| {
+        // The path starts from the sixth field, and may contain spaces.
+        let Some(path) = line.splitn(6, ' ').nth(5) else {
+            return false;
+        };
+        let Some(path) = path.trim_start().strip_suffix(" (deleted)") else {
+            return false;
+        };
+        CODE_PREFIXES.iter().any(|prefix| path.starts_with(prefix))
+    })
+}
+
+/// Get the system service from `/proc/<pid>/cgroup`, e.g. `0::/system.slice/ssh.service`.
+///
+/// Processes outside of system services, e.g. in user sessions, are ignored,
+/// as they can't be restarted as a unit.
+fn parse_system_unit(cgroup: &str) -> Option<&str> {
+    cgroup.lines().find_map(|line| {
+        let path = line.strip_prefix("0::/system.slice/")?;
+        path.split('/').find(|part| part.ends_with(".service"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{has_deleted_code, parse_system_unit};
+
+    #[test]
+    fn test_has_deleted_code() {
+        let maps = include_str!("assets/proc_maps_1");
+        assert!(has_deleted_code(maps));
+        let maps = maps
+            .lines()
+            .filter(|line| !line.contains("libc.so.6"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert!(!has_deleted_code(&maps));
+    }
+
+    #[test]
+    fn test_parse_system_unit() {
+        assert_eq!(
+            parse_system_unit("0::/system.slice/ssh.service\n"),
+            Some("ssh.service")
+        );
+        assert_eq!(
+            parse_system_unit("0::/system.slice/system-getty.slice/getty@tty1.service\n"),
+            Some("getty@tty1.service")
+        );
+        assert_eq!(
+            parse_system_unit("0::/user.slice/user-1000.slice/session-2.scope\n"),
+            None
+        );
+    }
+}
@@ -1,4 +1,7 @@
+use serde::ser::Error as _;
 use serde::{Serialize, Serializer, ser::SerializeMap};
+use time::OffsetDateTime;
+use time::format_description::well_known::Iso8601;
 
 pub fn serialize_as_map<S, K, V>(value: &[(K, V)], serializer: S) -> Result<S::Ok, S::Error>
 where
@@ -12,3 +15,23 @@ where
     }
     map.end()
 }
+
+pub fn serialize_timestamp<S: Serializer>(
+    datetime: &OffsetDateTime,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    datetime
+        .format(&Iso8601::DEFAULT)
+        .map_err(S::Error::custom)?
+        .serialize(serializer)
+}
+
+pub fn serialize_timestamp_opt<S: Serializer>(
+    datetime: &Option<OffsetDateTime>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match datetime {
+        Some(datetime) => serialize_timestamp(datetime, serializer),
+        None => serializer.serialize_none(),
+    }
+}
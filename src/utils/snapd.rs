@@ -0,0 +1,109 @@
+use anyhow::{Context, Error, bail};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::path::Path;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UnixStream;
+use tokio::time::timeout;
+
+const SNAPD_SOCKET: &str = "/run/snapd.socket";
+/// Maximum time to wait for a response, which may involve querying the Snap Store.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Whether snapd is available on this system.
+pub fn is_available() -> bool {
+    Path::new(SNAPD_SOCKET).exists()
+}
+
+/// Send a GET request to the snapd REST API, and return the result on success.
+///
+/// Errors of the given `not_found_kind` are taken as `None`.
+pub async fn get<T: DeserializeOwned>(
+    path: &str,
+    not_found_kind: Option<&str>,
+) -> Result<Option<T>, Error> {
+    let response = timeout(REQUEST_TIMEOUT, request(path))
+        .await
+        .context("Timed out waiting for snapd")??;
+    parse_response(&response, not_found_kind).with_context(|| format!("Failed to get {path}"))
+}
+
+async fn request(path: &str) -> Result<Vec<u8>, Error> {
+    let mut stream = UnixStream::connect(SNAPD_SOCKET)
+        .await
+        .context("Failed to connect to snapd")?;
+    // HTTP/1.0 makes snapd close the connection after the response without chunking the body.
+    let request = format!("GET {path} HTTP/1.0\r\nHost: localhost\r\n\r\n");
+    stream
+        .write_all(request.as_bytes())
+        .await
+        .context("Failed to send request to snapd")?;
+    let mut response = Vec::new();
+    stream
+        .read_to_end(&mut response)
+        .await
+        .context("Failed to read response from snapd")?;
+    Ok(response)
+}
+
+fn parse_response<T: DeserializeOwned>(
+    response: &[u8],
+    not_found_kind: Option<&str>,
+) -> Result<Option<T>, Error> {
+    let body_start = response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .context("Malformed HTTP response")?
+        + 4;
+    let response = serde_json::from_slice::<Response>(&response[body_start..])
+        .context("Failed to parse snapd response")?;
+    match response.kind.as_str() {
+        "sync" => Ok(Some(
+            serde_json::from_value(response.result).context("Failed to parse snapd result")?,
+        )),
+        "error" => {
+            let error = serde_json::from_value::<ErrorResult>(response.result)
+                .context("Failed to parse snapd error")?;
+            if not_found_kind.is_some() && error.kind.as_deref() == not_found_kind {
+                return Ok(None);
+            }
+            bail!("snapd returned error: {}", error.message)
+        }
+        kind => bail!("Unexpected snapd response type {kind}"),
+    }
+}
+
+#[derive(Deserialize)]
+struct Response {
+    #[serde(rename = "type")]
+    kind: String,
+    result: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct ErrorResult {
+    message: String,
+    kind: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_response;
+    use serde_json::Value;
+
+    #[test]
+    fn test_parse_response() {
+        let response = b"HTTP/1.0 200 OK\r\nContent-Type: application/json\r\n\r\n\
+            {\"type\":\"sync\",\"status-code\":200,\"status\":\"OK\",\"result\":[{\"name\":\"core22\"}]}";
+        let result = parse_response::<Value>(response, None).unwrap().unwrap();
+        assert_eq!(result[0]["name"], "core22");
+
+        let response = b"HTTP/1.0 404 Not Found\r\n\r\n\
+            {\"type\":\"error\",\"status-code\":404,\"result\":\
+            {\"message\":\"snap not found\",\"kind\":\"snap-not-found\"}}";
+        let result = parse_response::<Value>(response, Some("snap-not-found")).unwrap();
+        assert!(result.is_none());
+        assert!(parse_response::<Value>(response, None).is_err());
+    }
+}